@@ -0,0 +1,536 @@
+use std::{cell::RefCell, collections::VecDeque, fmt, future::Future, rc::Rc, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::channel::oneshot;
+use leptos::{
+    create_rw_signal, Callback, Resource, RwSignal, Signal, SignalGet, SignalSet, SignalUpdate,
+    StoredValue,
+};
+use rspotify::{
+    clients::BaseClient, http::HttpError, model::UserId, AuthCodePkceSpotify, ClientError, Token,
+};
+
+use crate::{diagnostics::Diagnostics, i18n::Msg};
+
+/// How many requests to Spotify may be in flight at once. Spotify rate limits per app over a
+/// rolling window, so firing a request per contributor at once quickly gets a large playlist
+/// throttled.
+pub const MAX_CONCURRENT_REQUESTS: usize = 5;
+
+/// How many requests to Spotify may start per second, keeps bursts of short requests under the
+/// rate limit too.
+pub const MAX_REQUESTS_PER_SECOND: u32 = 10;
+
+/// How often [`with_backoff_reporting`] tries a request before giving up.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// The wait before the first retry, doubled for every retry after it.
+const FIRST_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The longest [`with_backoff_reporting`] waits when Spotify says how long to wait, it can ask for hours
+/// when the app as a whole is over its limit. Asked to wait longer, the request fails right away.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// The HTTP status code of the response, if the request failed because Spotify answered with a
+/// non-success status.
+pub fn status_code(err: &ClientError) -> Option<u16> {
+    match err {
+        ClientError::Http(err) => match &**err {
+            HttpError::StatusCode(response) => Some(response.status().as_u16()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The name to show for a user, Spotify accounts don't need to have a display name.
+pub fn display_name(display_name: Option<String>, id: &UserId<'_>) -> String {
+    display_name.unwrap_or_else(|| id.to_string())
+}
+
+/// The user that is connected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentUser {
+    /// Their tracks are matched on this, display names need not be unique.
+    pub id: UserId<'static>,
+    pub name: String,
+}
+
+/// Loads the [`CurrentUser`] whenever the source, whether the user is connected, becomes `true`.
+pub type CurrentUserResource = Resource<bool, Option<CurrentUser>>;
+
+/// Whether trying again could help. Other client errors would be answered the same way.
+fn is_transient(err: &ClientError) -> bool {
+    match status_code(err) {
+        Some(status) => status == 429 || status >= 500,
+        None => matches!(err, ClientError::Http(_)),
+    }
+}
+
+/// How long Spotify asked to wait before trying again, from the `Retry-After` of a 429 Too Many
+/// Requests.
+fn retry_after(err: &ClientError) -> Option<Duration> {
+    match err {
+        ClientError::Http(err) => match &**err {
+            HttpError::StatusCode(response) if response.status().as_u16() == 429 => {
+                let seconds = response.headers().get("retry-after")?.to_str().ok()?;
+                seconds.trim().parse().ok().map(Duration::from_secs)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub async fn sleep(duration: Duration) {
+    let (sender, receiver) = oneshot::channel();
+    leptos::set_timeout(
+        move || {
+            sender.send(()).ok();
+        },
+        duration,
+    );
+    receiver.await.ok();
+}
+
+/// Runs `request` like [`with_backoff_reporting`], but only retries when Spotify never answered.
+/// For requests that can't be sent twice, like exchanging a login code: once Spotify answered, even
+/// with an error, the code may be spent.
+pub async fn with_network_backoff<T, F, Fut>(request: F) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    let unanswered = |err: &ClientError| status_code(err).is_none() && is_transient(err);
+    with_backoff_reporting(request, unanswered, |_| {}).await
+}
+
+/// Runs `request` until it succeeds, fails for a reason `retry` says retrying won't fix or
+/// [`MAX_ATTEMPTS`] is reached, waiting longer before each retry. When rate limited it waits as
+/// long as Spotify asks, calling `rate_limited` with `true` before waiting and with `false` after.
+async fn with_backoff_reporting<T, F, Fut>(
+    mut request: F,
+    retry: impl Fn(&ClientError) -> bool,
+    rate_limited: impl Fn(bool),
+) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match request().await {
+            Err(err) if attempt < MAX_ATTEMPTS && retry(&err) => {
+                let limited = status_code(&err) == Some(429);
+                let wait = match retry_after(&err) {
+                    Some(wait) if wait > MAX_RETRY_AFTER => return Err(err),
+                    Some(wait) => wait,
+                    None => backoff,
+                };
+                if limited {
+                    rate_limited(true);
+                }
+                sleep(wait).await;
+                if limited {
+                    rate_limited(false);
+                }
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Runs requests with the connected user's token, refreshing the token once when Spotify answers
+/// 401 Unauthorized. Provided as context by `main`.
+#[derive(Clone, Copy)]
+pub struct Session {
+    /// Persists a refreshed token.
+    store_token: Callback<Token>,
+    diagnostics: Diagnostics,
+    /// Lets one request refresh at a time, Spotify hands out a new refresh token on every refresh
+    /// and the old one stops working.
+    refresh_lock: StoredValue<Limiter>,
+    /// Requests that haven't finished yet, including their retries and token refreshes.
+    running: RwSignal<usize>,
+    /// Requests waiting because Spotify answered 429 Too Many Requests.
+    rate_limited: RwSignal<usize>,
+    /// Set when the token can't be renewed and the user has to log in again.
+    expired: RwSignal<bool>,
+}
+
+/// Counts a request as running until dropped.
+struct Running(RwSignal<usize>);
+
+impl Running {
+    fn new(running: RwSignal<usize>) -> Self {
+        running.update(|running| *running += 1);
+        Self(running)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0
+            .update(|running| *running = running.saturating_sub(1));
+    }
+}
+
+impl Session {
+    pub fn new(store_token: Callback<Token>, diagnostics: Diagnostics) -> Self {
+        Self {
+            store_token,
+            diagnostics,
+            refresh_lock: StoredValue::new(Limiter::new(1)),
+            running: create_rw_signal(0),
+            rate_limited: create_rw_signal(0),
+            expired: create_rw_signal(false),
+        }
+    }
+
+    /// Whether the user has to log in again. Whatever sends them to the login clears it, so the
+    /// next time it's set is a change again.
+    pub fn expired(self) -> RwSignal<bool> {
+        self.expired
+    }
+
+    /// Whether any request is running, for the progress bar.
+    pub fn busy(self) -> Signal<bool> {
+        let running = self.running;
+        Signal::derive(move || running.get() > 0)
+    }
+
+    /// Whether any request is waiting for Spotify's rate limit to pass.
+    pub fn rate_limited(self) -> Signal<bool> {
+        let rate_limited = self.rate_limited;
+        Signal::derive(move || rate_limited.get() > 0)
+    }
+
+    fn report_rate_limited(self, waiting: bool) {
+        self.rate_limited.update(|rate_limited| {
+            *rate_limited = if waiting {
+                *rate_limited + 1
+            } else {
+                rate_limited.saturating_sub(1)
+            }
+        });
+    }
+
+    /// Runs `request`, which has to use `spotify`, with [`with_backoff_reporting`]. An expired token is
+    /// refreshed first. If Spotify still doesn't accept the token it is refreshed and `request` is
+    /// tried again, if that doesn't help the user has to log in again.
+    pub async fn request<T, F, Fut>(
+        self,
+        spotify: &AuthCodePkceSpotify,
+        mut request: F,
+    ) -> Result<T, ClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let _running = Running::new(self.running);
+        // Spotify would answer 401, refreshing first saves a round trip.
+        if is_expired(spotify).await {
+            let _permit = self.refresh_lock.get_value().acquire(Priority::High).await;
+            // Unless another request refreshed it in the meantime.
+            if is_expired(spotify).await {
+                if let Err(err) = self.refresh(spotify).await {
+                    self.refresh_failed(&err);
+                    return Err(err);
+                }
+            }
+        }
+
+        let used_token = access_token(spotify).await;
+        let report = |waiting| self.report_rate_limited(waiting);
+        match with_backoff_reporting(&mut request, is_transient, report).await {
+            Err(err) if status_code(&err) == Some(401) => {}
+            result => return result,
+        }
+
+        let refreshed = {
+            let _permit = self.refresh_lock.get_value().acquire(Priority::High).await;
+            if access_token(spotify).await != used_token {
+                // Another request refreshed it in the meantime.
+                Ok(())
+            } else {
+                self.refresh(spotify).await
+            }
+        };
+        let result = match refreshed {
+            Ok(()) => with_backoff_reporting(&mut request, is_transient, report).await,
+            Err(err) => {
+                self.refresh_failed(&err);
+                return Err(err);
+            }
+        };
+        if let Err(err) = &result {
+            if status_code(err) == Some(401) {
+                self.logged_out(err);
+            }
+        }
+        result
+    }
+
+    /// Only when Spotify refuses the refresh token the user has to log in again. Other failures,
+    /// like a dropped connection, fail just the request, it can be tried again.
+    fn refresh_failed(self, err: &ClientError) {
+        if matches!(status_code(err), Some(400 | 401)) {
+            self.logged_out(err);
+        } else {
+            self.diagnostics
+                .record("session: renewing the token failed", Some(err.to_string()));
+        }
+    }
+
+    /// The token can't be renewed, has the user log in again.
+    fn logged_out(self, err: &ClientError) {
+        self.diagnostics.record(
+            "session: the token could not be renewed",
+            Some(err.to_string()),
+        );
+        self.expired.set(true);
+    }
+
+    async fn refresh(self, spotify: &AuthCodePkceSpotify) -> Result<(), ClientError> {
+        spotify.refresh_token().await?;
+        if let Ok(token) = spotify.get_token().lock().await {
+            if let Some(token) = token.clone() {
+                (self.store_token)(token);
+            }
+        }
+        self.diagnostics
+            .record("session: refreshed the token", None);
+        Ok(())
+    }
+}
+
+async fn is_expired(spotify: &AuthCodePkceSpotify) -> bool {
+    match spotify.get_token().lock().await {
+        Ok(token) => token.as_ref().is_some_and(Token::is_expired),
+        Err(_) => false,
+    }
+}
+
+async fn access_token(spotify: &AuthCodePkceSpotify) -> Option<String> {
+    let token = spotify.get_token();
+    let token = token.lock().await.ok()?;
+    token.as_ref().map(|token| token.access_token.clone())
+}
+
+/// A failed request. Unlike [`ClientError`] this can be cloned into a resource and shown by an
+/// `ErrorBoundary`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub status: Option<u16>,
+    pub message: String,
+    /// Explains to the user what they can do about it, shown below the message.
+    pub hint: Option<Msg>,
+}
+
+impl ApiError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            status: None,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(self, hint: Msg) -> Self {
+        Self {
+            hint: Some(hint),
+            ..self
+        }
+    }
+}
+
+impl From<ClientError> for ApiError {
+    fn from(err: ClientError) -> Self {
+        Self {
+            status: status_code(&err),
+            message: err.to_string(),
+            hint: None,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Which requests go first when they have to wait for the [`Limiter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// What the user is waiting to see.
+    High,
+    /// Details that fill in what is already shown, like the names of the users.
+    Low,
+}
+
+/// An async semaphore shared, through the context, by everything that talks to Spotify. Waiting
+/// requests are queued by [`Priority`], and can be spaced out with [`Limiter::with_rate`].
+#[derive(Clone)]
+pub struct Limiter {
+    state: Rc<RefCell<LimiterState>>,
+    queued: RwSignal<usize>,
+}
+
+struct LimiterState {
+    available: usize,
+    high: VecDeque<oneshot::Sender<()>>,
+    low: VecDeque<oneshot::Sender<()>>,
+    /// Time between the starts of two requests, `None` starts them as soon as there is a slot.
+    interval: Option<TimeDelta>,
+    /// When the next request may start.
+    next_start: DateTime<Utc>,
+}
+
+impl LimiterState {
+    /// Waiters whose future was dropped in the meantime don't count.
+    fn queued(&self) -> usize {
+        self.high
+            .iter()
+            .chain(&self.low)
+            .filter(|sender| !sender.is_canceled())
+            .count()
+    }
+}
+
+/// Gives its slot to the next waiting request when dropped.
+pub struct Permit {
+    state: Rc<RefCell<LimiterState>>,
+    queued: RwSignal<usize>,
+}
+
+/// Where a waiting request receives its [`Permit`]. If the request is dropped after the permit was
+/// sent but before it took it, the permit is passed on from here, or the slot would be lost.
+struct Handover {
+    receiver: oneshot::Receiver<()>,
+    state: Rc<RefCell<LimiterState>>,
+    queued: RwSignal<usize>,
+}
+
+impl Limiter {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(LimiterState {
+                available: permits,
+                high: VecDeque::new(),
+                low: VecDeque::new(),
+                interval: None,
+                next_start: Utc::now(),
+            })),
+            queued: create_rw_signal(0),
+        }
+    }
+
+    /// Lets at most `per_second` requests start every second.
+    pub fn with_rate(self, per_second: u32) -> Self {
+        let interval = TimeDelta::milliseconds(1000 / i64::from(per_second.max(1)));
+        self.state.borrow_mut().interval = Some(interval);
+        self
+    }
+
+    /// How many requests are waiting for a slot.
+    pub fn queued(&self) -> Signal<usize> {
+        self.queued.into()
+    }
+
+    pub async fn acquire(&self, priority: Priority) -> Permit {
+        let (waiting, queued) = {
+            let mut state = self.state.borrow_mut();
+            if state.available > 0 {
+                state.available -= 1;
+                (None, None)
+            } else {
+                let (sender, receiver) = oneshot::channel();
+                match priority {
+                    Priority::High => state.high.push_back(sender),
+                    Priority::Low => state.low.push_back(sender),
+                }
+                (Some(receiver), Some(state.queued()))
+            }
+        };
+        if let Some(queued) = queued {
+            self.queued.set(queued);
+        }
+        if let Some(receiver) = waiting {
+            // The permit is handed over directly by the one that is dropped.
+            let mut handover = Handover {
+                receiver,
+                state: self.state.clone(),
+                queued: self.queued,
+            };
+            (&mut handover.receiver).await.ok();
+        }
+        let permit = Permit {
+            state: self.state.clone(),
+            queued: self.queued,
+        };
+
+        // Claims the next start time right away, so requests that get a slot together still
+        // start one after the other.
+        let wait = {
+            let mut state = self.state.borrow_mut();
+            state.interval.map(|interval| {
+                let now = Utc::now();
+                let start = state.next_start.max(now);
+                state.next_start = start + interval;
+                start - now
+            })
+        };
+        if let Some(wait) = wait.and_then(|wait| wait.to_std().ok()) {
+            if !wait.is_zero() {
+                sleep(wait).await;
+            }
+        }
+        permit
+    }
+
+    /// Runs `request` once a slot is free.
+    pub async fn run<F: Future>(&self, priority: Priority, request: F) -> F::Output {
+        let _permit = self.acquire(priority).await;
+        request.await
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let queued = {
+            let mut state = self.state.borrow_mut();
+            let mut handed_over = false;
+            // Waiters whose future was dropped in the meantime can't take the permit.
+            while let Some(sender) = state.high.pop_front().or_else(|| state.low.pop_front()) {
+                if sender.send(()).is_ok() {
+                    handed_over = true;
+                    break;
+                }
+            }
+            if !handed_over {
+                state.available += 1;
+            }
+            state.queued()
+        };
+        self.queued.set(queued);
+    }
+}
+
+impl Drop for Handover {
+    fn drop(&mut self) {
+        self.receiver.close();
+        // Only `Some` if the permit was sent but never received.
+        if let Ok(Some(())) = self.receiver.try_recv() {
+            drop(Permit {
+                state: self.state.clone(),
+                queued: self.queued,
+            });
+        }
+    }
+}
@@ -3,329 +3,941 @@ use std::{
     collections::{HashMap, HashSet},
 };
 
-use chrono::{TimeDelta, Utc};
-use futures::{future::join_all, stream::TryStreamExt};
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::future::join_all;
 use leptos::{
-    component, create_local_resource, expect_context, view, For, IntoView, Memo, SignalGet,
-    SignalGetUntracked, SignalWith, Suspense,
+    component, create_effect, create_local_resource, create_memo, create_rw_signal, create_signal,
+    ev::{MouseEvent, SubmitEvent},
+    event_target_checked, event_target_value, expect_context, spawn_local, view, Callback,
+    ErrorBoundary, For, IntoView, Memo, Show, Signal, SignalGet, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith, SignalWithUntracked, Suspense, WriteSignal,
 };
-use leptos_router::{use_params_map, Outlet, A};
-use random_color::RandomColor;
-use rgb::RGB8;
+use leptos_router::{
+    use_location, use_navigate, use_params_map, use_query_map, NavigateOptions, Outlet, A,
+};
+use leptos_use::{storage::use_local_storage, utils::JsonCodec};
 use rspotify::{
     clients::{BaseClient, OAuthClient},
-    model::{PlayableItem, PlaylistId},
-    AuthCodePkceSpotify,
+    model::{FullPlaylist, Image, PlaylistId, SimplifiedPlaylist, UserId},
+    AuthCodePkceSpotify, ClientError,
 };
 
-#[component]
-pub fn MainPage() -> impl IntoView {
-    let spotify = expect_context::<Memo<AuthCodePkceSpotify>>();
+use crate::{
+    api::{self, ApiError, CurrentUserResource, Limiter, Priority, Session},
+    app_path,
+    error::ErrorCard,
+    export::ExportButtons,
+    i18n::{use_i18n, Msg},
+    info::{build_playlist_info, focus, PlaylistInfo, UserKey, UserLookup},
+    legend::Legend,
+    race::BarChartRace,
+    ribbon::{CompactRibbon, Ribbon},
+    settings::{RibbonView, Settings, SettingsPanel},
+    share::ShareQr,
+    snapshot::{Snapshot, SnapshotDiff},
+    summary::{Stats, Summary},
+    table::{TrackTable, UserTable},
+    timeline::Timeline,
+    BASE_PATH,
+};
 
-    let playlists = create_local_resource(
-        || (),
-        move |_| async move {
-            let spotify = spotify.get_untracked();
-            let playlists_stream = spotify.current_user_playlists();
+/// The full path of a playlist's page, from its bare id. The `A` around it only takes over plain
+/// left clicks, so with a real path middle and ctrl clicks open the playlist in a new tab.
+pub fn playlist_href(id: &str) -> String {
+    app_path(&format!("/{id}"))
+}
 
-            let v: Vec<_> = playlists_stream.try_collect().await.unwrap();
+/// Size in css pixels of a cover on the selection page.
+const COVER_SIZE: u32 = 64;
+
+/// Size in css pixels of an avatar in the ribbon headers, a bit bigger than shown for sharp edges.
+const AVATAR_SIZE: u32 = 32;
+
+/// The smallest image that is still at least `size` wide, or the biggest one if they are all
+/// smaller. Images uploaded by users often come without a size, those are only used as a last
+/// resort.
+fn image_url(images: &[Image], size: u32) -> Option<&str> {
+    let sized = images
+        .iter()
+        .filter_map(|image| Some((image.width?, image)));
+    sized
+        .clone()
+        .filter(|&(width, _)| width >= size)
+        .min_by_key(|&(width, _)| width)
+        .or_else(|| sized.max_by_key(|&(width, _)| width))
+        .map(|(_, image)| image)
+        .or_else(|| images.first())
+        .map(|image| image.url.as_str())
+}
+
+/// The most tracks Spotify returns in one page.
+const TRACKS_PAGE_SIZE: u32 = 100;
+
+/// The most playlists Spotify returns in one page.
+const PLAYLISTS_PAGE_SIZE: u32 = 50;
+
+/// How long the ids Spotify hands out are.
+const SPOTIFY_ID_LENGTH: usize = 22;
+
+/// Takes a playlist id, a `spotify:playlist:` uri or an `open.spotify.com` link to a playlist. Share
+/// links come with a `?si=` and links from some countries with an `/intl-xx` in front, both are
+/// ignored.
+fn parse_playlist_input(input: &str) -> Option<PlaylistId<'static>> {
+    let input = input.trim();
+    let rest = match (
+        input.split_once("/playlist/"),
+        input.strip_prefix("spotify:playlist:"),
+    ) {
+        (Some((_, rest)), _) | (None, Some(rest)) => rest,
+        (None, None) => input,
+    };
+    let id = rest.split(['?', '#', '/']).next().unwrap_or_default();
+    // Anything else can't be a Spotify id, no need to ask Spotify about it.
+    if id.len() != SPOTIFY_ID_LENGTH || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    PlaylistId::from_id(id).ok().map(|id| id.into_static())
+}
 
-            v
-        },
-    );
+/// Opens any playlist, also ones that aren't in the user's library and thus not in the list.
+#[component]
+fn OpenPlaylist() -> impl IntoView {
+    let i18n = use_i18n();
+    let (input, set_input) = create_signal(String::new());
+    let (invalid, set_invalid) = create_signal(false);
+
+    let open = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        match input.with(|input| parse_playlist_input(input)) {
+            Some(id) => {
+                set_invalid(false);
+                use_navigate()(
+                    &playlist_href(Borrow::<str>::borrow(&id)),
+                    NavigateOptions::default(),
+                );
+            }
+            None => set_invalid(true),
+        }
+    };
 
     view! {
-        <div class="selection">
-            <h1>Your playlists:</h1>
-            <Suspense fallback=|| {
-                view! {
-                    <h1>Loading</h1>
-                    <A href="/collab-playlist/login">If this is taking a long time, click here.</A>
+        <form class="open-playlist" on:submit=open>
+            <input
+                type="text"
+                placeholder=move || i18n.t(Msg::OpenPlaylistPlaceholder)
+                prop:value=input
+                on:input=move |ev| {
+                    set_input(event_target_value(&ev));
+                    set_invalid(false);
                 }
-            }>
-                <div class="selection-buttons">
-                    <For
-                        each=move || playlists().unwrap_or_default()
-                        key=|playlist| playlist.id.clone()
-                        let:playlist
-                    >
-                        <A
-                            href={Borrow::<str>::borrow(&playlist.id.clone()).to_owned()}
-                            class="selection-button"
-                        >
-                            {playlist.name.clone()}
-                            ": "
-                            {if playlist.collaborative { "collaborative" } else { "solo" }}
-
-                        </A>
-                    </For>
-                </div>
-            </Suspense>
-        </div>
-        <Outlet/>
+            />
+            <button type="submit">{move || i18n.t(Msg::OpenPlaylist)}</button>
+            <Show when=invalid>
+                <span class="open-playlist-invalid">{move || i18n.t(Msg::InvalidPlaylistId)}</span>
+            </Show>
+        </form>
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct TrackInfo {
-    name: String,
-    duration: TimeDelta,
-    relative_size: f64,
-    color: RGB8,
-    age: f64,
+/// Tells the user whether logging in again or retrying is what helps.
+fn playlists_error(err: ClientError) -> ApiError {
+    let err = ApiError::from(err);
+    match err.status {
+        Some(401) => err.with_hint(Msg::LoginExpiredHint),
+        None | Some(429 | 500..) => err.with_hint(Msg::ConnectionHint),
+        _ => err,
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct UserInfo {
-    name: String,
-    relative_size: f64,
-    total_duration: TimeDelta,
-    amount_of_tracks: u64,
-    color: RGB8,
+/// A playlist as listed on the main page, and as kept in the playlist cache.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PlaylistEntry {
+    pub id: String,
+    pub name: String,
+    pub collaborative: bool,
+    pub cover: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct PlaylistInfo {
-    name: String,
-    total_duration: TimeDelta,
-
-    tracks: Vec<TrackInfo>,
-    users: Vec<UserInfo>,
+impl PlaylistEntry {
+    fn new(playlist: &SimplifiedPlaylist) -> Self {
+        Self {
+            id: Borrow::<str>::borrow(&playlist.id).to_owned(),
+            name: playlist.name.clone(),
+            collaborative: playlist.collaborative,
+            cover: image_url(&playlist.images, COVER_SIZE).map(str::to_owned),
+        }
+    }
 }
 
-fn display_duration(dur: &TimeDelta) -> String {
-    let minutes = dur.num_minutes();
-    let seconds = dur.num_seconds() - minutes * 60;
-    format!("{minutes}:{seconds}")
+/// The playlists last listed for every user that logged in, by their id.
+type PlaylistCache = HashMap<String, Vec<PlaylistEntry>>;
+
+/// The [`PlaylistCache`], kept in local storage under `playlists`. Shown right away on the next
+/// visit while the playlists are loaded again.
+pub fn use_playlist_cache() -> (
+    Signal<PlaylistCache>,
+    WriteSignal<PlaylistCache>,
+    impl Fn() + Clone,
+) {
+    use_local_storage::<PlaylistCache, JsonCodec>("playlists")
 }
 
+/// Shows the first page of the user's playlists as soon as it's there, the rest are only loaded
+/// when asked for. Until the first page is there the playlists from the last visit are shown.
 #[component]
-pub fn Playlist() -> impl IntoView {
-    let params = use_params_map();
-    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
-
+pub fn MainPage() -> impl IntoView {
     let spotify = expect_context::<Memo<AuthCodePkceSpotify>>();
+    let limiter = expect_context::<Limiter>();
+    let session = expect_context::<Session>();
+    let current_user = expect_context::<CurrentUserResource>();
+    let (cache, set_cache, _) = use_playlist_cache();
+
+    // The pages after the first, in the order they were loaded.
+    let more = create_rw_signal(Vec::<SimplifiedPlaylist>::new());
+    // Where the next page starts, `None` once everything is loaded.
+    let next_offset = create_rw_signal(None::<u32>);
+    let loading_more = create_rw_signal(false);
+    let more_failed = create_rw_signal(None::<ApiError>);
+
+    let playlists = create_local_resource(|| (), {
+        let limiter = limiter.clone();
+        move |_| {
+            let limiter = limiter.clone();
+            async move {
+                let spotify = spotify.get_untracked();
+                more.set(Vec::new());
+                next_offset.set(None);
+                more_failed.set(None);
+                let page = session
+                    .request(&spotify, || {
+                        limiter.run(
+                            Priority::High,
+                            spotify
+                                .current_user_playlists_manual(Some(PLAYLISTS_PAGE_SIZE), Some(0)),
+                        )
+                    })
+                    .await
+                    .map_err(playlists_error)?;
+                next_offset.set(
+                    page.next
+                        .is_some()
+                        .then(|| page.offset + page.items.len() as u32),
+                );
+                Ok::<_, ApiError>((page.items, page.total))
+            }
+        }
+    });
+    let fresh = move || playlists.with(|playlists| matches!(playlists, Some(Ok(_))));
 
-    let raw_data = create_local_resource(id, move |id| async move {
-        let spotify = spotify.get_untracked();
-
-        let id = PlaylistId::from_id(id).unwrap();
-
-        let playlist = spotify.playlist(id, None, None).await.unwrap();
-
-        let mut users = HashSet::new();
+    let load_more = Callback::new(move |()| {
+        let Some(offset) = next_offset.get_untracked() else {
+            return;
+        };
+        if loading_more.get_untracked() {
+            return;
+        }
+        loading_more.set(true);
+        more_failed.set(None);
+        let limiter = limiter.clone();
+        spawn_local(async move {
+            let spotify = spotify.get_untracked();
+            let page = session
+                .request(&spotify, || {
+                    limiter.run(
+                        Priority::High,
+                        spotify
+                            .current_user_playlists_manual(Some(PLAYLISTS_PAGE_SIZE), Some(offset)),
+                    )
+                })
+                .await;
+            match page {
+                Ok(page) => {
+                    next_offset.set(
+                        page.next
+                            .is_some()
+                            .then(|| page.offset + page.items.len() as u32),
+                    );
+                    more.update(|more| more.extend(page.items));
+                }
+                Err(err) => more_failed.set(Some(playlists_error(err))),
+            }
+            loading_more.set(false);
+        });
+    });
 
-        for t in &playlist.tracks.items {
-            if let Some(added_by) = &t.added_by {
-                users.insert(added_by.id.clone());
+    let user = move || {
+        current_user.with(|user| {
+            user.as_ref()
+                .and_then(Option::as_ref)
+                .map(|user| Borrow::<str>::borrow(&user.id).to_owned())
+        })
+    };
+    // Playlists added or removed while paging shift the pages, so the same playlist can come twice.
+    let loaded = create_memo(move |_| {
+        playlists.with(|playlists| match playlists {
+            Some(Ok((first, _))) => {
+                let mut seen = HashSet::new();
+                more.with(|more| {
+                    first
+                        .iter()
+                        .chain(more)
+                        .filter(|playlist| seen.insert(playlist.id.clone()))
+                        .map(PlaylistEntry::new)
+                        .collect::<Vec<_>>()
+                })
             }
+            _ => user()
+                .and_then(|user| cache.with(|cache| cache.get(&user).cloned()))
+                .unwrap_or_default(),
+        })
+    });
+    // Only what came from Spotify is kept, so a failed load doesn't wipe the cache.
+    create_effect(move |_| {
+        if !fresh() {
+            return;
         }
-
-        let user_names = join_all(users.into_iter().map(|id| (id, spotify.clone())).map(
-            |(user_id, spotify)| async move {
-                let Ok(user) = spotify.user(user_id.clone()).await else {
-                    return (user_id, "Faild to get user".to_owned());
-                };
-                let name = user.display_name.unwrap_or_else(|| user.id.to_string());
-                (user_id, name)
-            },
-        ))
-        .await
-        .into_iter()
-        .collect::<HashMap<_, _>>();
-        (playlist, user_names)
+        let Some(user) = user() else {
+            return;
+        };
+        let playlists = loaded.get();
+        set_cache.update(|cache| {
+            cache.insert(user, playlists);
+        });
     });
+    let total = move || {
+        playlists.with(|playlists| match playlists {
+            Some(Ok((_, total))) => *total,
+            _ => 0,
+        })
+    };
 
-    let data = move || {
-        let Some((playlist, user_names)) = raw_data.get() else {
-            return None;
+    // The filter lives in the query, so it's still there when coming back from a playlist. Every
+    // change replaces the history entry, typing doesn't leave one behind for every key.
+    let query = use_query_map();
+    let location = use_location();
+    let navigate = use_navigate();
+    let set_query = Callback::new(move |(key, value): (&'static str, Option<String>)| {
+        let mut query = query.get_untracked();
+        match value {
+            Some(value) => query.insert(key.to_owned(), value),
+            None => query.remove(key),
         };
+        let path = location.pathname.get_untracked();
+        navigate(
+            &format!("{path}{}", query.to_query_string()),
+            NavigateOptions {
+                replace: true,
+                scroll: false,
+                ..NavigateOptions::default()
+            },
+        );
+    });
 
-        let name = playlist.name;
+    // Only filters the already loaded playlists, lowercased once here instead of for every playlist.
+    let filter = create_memo(move |_| query.with(|query| query.get("filter").cloned()));
+    let needle = create_memo(move |_| {
+        filter.with(|filter| filter.as_deref().unwrap_or_default().trim().to_lowercase())
+    });
+    let settings = expect_context::<Signal<Settings>>();
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+    // The query wins over the setting, which is only where a fresh visit starts.
+    let collaborative_only = move || {
+        query
+            .with(|query| query.get("collaborative").map(|value| value == "1"))
+            .unwrap_or_else(|| settings.with(|s| s.collaborative_only))
+    };
+    let matching = create_memo(move |_| {
+        needle.with(|needle| {
+            loaded.with(|loaded| {
+                loaded
+                    .iter()
+                    .filter(|playlist| playlist.name.to_lowercase().contains(needle))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+        })
+    });
+    let shown = create_memo(move |_| {
+        let collaborative_only = collaborative_only();
+        matching.with(|matching| {
+            matching
+                .iter()
+                .filter(|playlist| !collaborative_only || playlist.collaborative)
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    });
+    let hidden = move || matching.with(Vec::len) - shown.with(Vec::len);
 
-        let mut total_duration = TimeDelta::default();
-        let mut user_id_to_track = HashMap::new();
+    let embed = use_embed();
+    let i18n = use_i18n();
 
-        for item in playlist.tracks.items {
-            match item.track {
-                Some(PlayableItem::Track(track)) => {
-                    total_duration += track.duration;
-                    user_id_to_track
-                        .entry(item.added_by.map(|u| u.id))
-                        .or_insert_with(Vec::new)
-                        .push((item.added_at, track));
-                }
-                _ => {}
+    let failed = move || {
+        more_failed.get().map(|err| {
+            view! {
+                <div class="selection-partial">
+                    <span title=err.to_string()>{move || i18n.t(Msg::SomePlaylistsFailed)}</span>
+                    <button on:click=move |_| load_more(())>{move || i18n.t(Msg::Retry)}</button>
+                </div>
             }
-        }
-
-        let now = Utc::now();
-        let mut data = user_id_to_track
-            .into_iter()
-            .map(|(user_id, groups)| {
-                let color = RandomColor::new()
-                    .seed(
-                        user_id
-                            .as_ref()
-                            .map(|id| Borrow::<str>::borrow(id))
-                            .unwrap_or_default(),
-                    )
-                    .to_rgb_array();
-                let color: RGB8 = color.into();
+        })
+    };
 
-                let mut user_tracks = groups
-                    .into_iter()
-                    .map(|(added_at, track)| {
-                        let age = now.clone().signed_duration_since(added_at.unwrap_or(now));
-                        let age = (age.num_days() as f64 / 200.0).clamp(0.0, 1.0);
-
-                        TrackInfo {
-                            name: track.name,
-                            duration: track.duration,
-                            relative_size: track.duration.num_milliseconds() as f64
-                                / total_duration.num_milliseconds() as f64,
-                            color: color.clone(),
-                            age,
+    view! {
+        <Show when=move || !embed()>
+            <div class="selection">
+                <OpenPlaylist/>
+                <h1>{move || i18n.t(Msg::YourPlaylists)}</h1>
+                // With cached playlists to show, loading happens in the background.
+                <Suspense fallback=move || {
+                    loaded
+                        .with(Vec::is_empty)
+                        .then(|| {
+                            view! {
+                                <h1>{move || i18n.t(Msg::Loading)}</h1>
+                                <A href=app_path("/login")>{move || i18n.t(Msg::TakingLong)}</A>
+                            }
+                        })
+                }>
+                    <ErrorBoundary fallback=move |errors| {
+                        view! { <ErrorCard errors=errors retry=move |_| playlists.refetch()/> }
+                    }>{move || playlists.get().map(|playlists| playlists.map(|_| ()))}</ErrorBoundary>
+                </Suspense>
+                <Show when=move || fresh() || !loaded.with(Vec::is_empty)>
+                    <input
+                        class="selection-filter"
+                        type="search"
+                        placeholder=move || i18n.t(Msg::FilterPlaylists)
+                        prop:value=move || filter().unwrap_or_default()
+                        on:input=move |ev| {
+                            let filter = event_target_value(&ev);
+                            set_query(("filter", (!filter.is_empty()).then_some(filter)));
                         }
-                    })
-                    .collect::<Vec<_>>();
+                    />
+                    <label class="selection-collaborative">
+                        <input
+                            type="checkbox"
+                            prop:checked=collaborative_only
+                            on:change=move |ev| {
+                                let checked = event_target_checked(&ev);
+                                set_settings.update(|s| s.collaborative_only = checked);
+                                set_query(("collaborative", Some(u8::from(checked).to_string())));
+                            }
+                        />
+                        {move || i18n.t(Msg::CollaborativeOnly)}
+                    </label>
+                    <Show when=move || hidden() != 0>
+                        <span class="selection-hidden">
+                            {move || i18n.fill(Msg::HiddenSoloPlaylists, &[&hidden()])}
+                        </span>
+                    </Show>
+                    <Show when=move || !needle.with(String::is_empty) && shown.with(Vec::is_empty)>
+                        <p class="selection-empty">{move || i18n.t(Msg::NoMatchingPlaylists)}</p>
+                    </Show>
+                    <div class="selection-buttons">
+                        <For each=shown key=|playlist| playlist.id.clone() let:playlist>
+                            // The query goes along, so the way back has the same filter.
+                            <A
+                                href={
+                                    let path = playlist_href(&playlist.id);
+                                    move || format!("{path}{}", query.with(|query| query.to_query_string()))
+                                }
+                                class="selection-button"
+                            >
+                                {match playlist.cover.clone() {
+                                    Some(url) => {
+                                        view! {
+                                            <img
+                                                class="selection-cover"
+                                                src=url
+                                                alt=""
+                                                loading="lazy"
+                                            />
+                                        }
+                                            .into_view()
+                                    }
+                                    None => {
+                                        view! {
+                                            <span class="selection-cover selection-cover-placeholder">
+                                                "♫"
+                                            </span>
+                                        }
+                                            .into_view()
+                                    }
+                                }}
+                                <span class="selection-name">{playlist.name.clone()}</span>
+                                {
+                                    let (icon, msg) = if playlist.collaborative {
+                                        ("👥", Msg::Collaborative)
+                                    } else {
+                                        ("👤", Msg::Solo)
+                                    };
+                                    view! {
+                                        <span
+                                            class="selection-kind"
+                                            role="img"
+                                            title=move || i18n.t(msg)
+                                            aria-label=move || i18n.t(msg)
+                                        >
+                                            {icon}
+                                        </span>
+                                    }
+                                }
+
+                            </A>
+                        </For>
+                    </div>
+                    {failed}
+                    <Show when=move || {
+                        next_offset.with(Option::is_some) && more_failed.with(Option::is_none)
+                    }>
+                        <button
+                            class="selection-more"
+                            prop:disabled=loading_more
+                            on:click=move |_| load_more(())
+                        >
+                            {move || {
+                                i18n.fill(
+                                    Msg::LoadMorePlaylists,
+                                    &[&loaded.with(Vec::len), &total()],
+                                )
+                            }}
+                        </button>
+                    </Show>
+                </Show>
+            </div>
+        </Show>
+        <Outlet/>
+    }
+}
 
-                user_tracks.sort_unstable_by(|a, b| a.duration.cmp(&b.duration));
+/// Whether the page was opened with `?embed=1`. In that mode only the ribbon is rendered, so the
+/// page can be put in an iframe.
+pub fn use_embed() -> Memo<bool> {
+    let query = use_query_map();
+    create_memo(move |_| query.with(|query| query.get("embed").is_some_and(|embed| embed == "1")))
+}
 
-                let user_name = user_id
-                    .and_then(|id| user_names.get(&id).cloned())
-                    .unwrap_or_else(|| "Unknow".to_owned());
+/// How long a looked up user name is used before looking it up again, users can rename themselves.
+const USER_NAME_MAX_AGE_DAYS: i64 = 7;
 
-                let user_total_duration: TimeDelta = user_tracks.iter().map(|t| &t.duration).sum();
+/// A user name as it was when it was looked up, kept in local storage under `user-names`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+struct CachedName {
+    name: String,
+    /// Names cached before avatars were shown have none, until they expire.
+    #[serde(default)]
+    avatar: Option<String>,
+    looked_up: DateTime<Utc>,
+}
 
-                let user = UserInfo {
-                    name: user_name,
-                    relative_size: user_total_duration.num_milliseconds() as f64
-                        / total_duration.num_milliseconds() as f64,
-                    total_duration: user_total_duration,
-                    amount_of_tracks: user_tracks.len() as u64,
-                    color,
-                };
-                (user, user_tracks)
-            })
-            .collect::<Vec<_>>();
+impl CachedName {
+    fn is_fresh(&self) -> bool {
+        Utc::now().signed_duration_since(self.looked_up) < TimeDelta::days(USER_NAME_MAX_AGE_DAYS)
+    }
+}
+
+#[component]
+pub fn Playlist() -> impl IntoView {
+    let params = use_params_map();
+    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+    let embed = use_embed();
+    let i18n = use_i18n();
+
+    let spotify = expect_context::<Memo<AuthCodePkceSpotify>>();
+    let limiter = expect_context::<Limiter>();
+    let session = expect_context::<Session>();
+    // Kept across retries and visits, so those only look up the users that failed or are new.
+    let (user_cache, set_user_cache, _) =
+        use_local_storage::<HashMap<String, CachedName>, JsonCodec>("user-names");
+
+    // Tracks loaded out of the total, while more pages are loading.
+    let (progress, set_progress) = create_signal(None::<(usize, usize)>);
+    // Users looked up out of all contributors, while their names are being looked up.
+    let (user_progress, set_user_progress) = create_signal(None::<(usize, usize)>);
+
+    let raw_data = create_local_resource(id, move |id| {
+        let limiter = limiter.clone();
+        async move {
+            let spotify = spotify.get_untracked();
+            // Left over when loading the previous playlist failed halfway.
+            set_progress(None);
+            set_user_progress(None);
+
+            // A typo in the address rather than anything Spotify said, so no status.
+            let id = PlaylistId::from_id(id).map_err(|err| {
+                ApiError::new(err.to_string()).with_hint(Msg::MalformedPlaylistId)
+            })?;
+
+            let mut playlist = session
+                .request(&spotify, || {
+                    limiter.run(Priority::High, spotify.playlist(id.clone(), None, None))
+                })
+                .await
+                .map_err(|err| {
+                    let err = ApiError::from(err);
+                    // Spotify answers both when the playlist is private to someone else.
+                    match err.status {
+                        Some(403 | 404) => err.with_hint(Msg::NoPlaylistAccess),
+                        _ => err,
+                    }
+                })?;
+
+            // The playlist only comes with the first page of its tracks.
+            let total = playlist.tracks.total as usize;
+            while playlist.tracks.items.len() < total {
+                set_progress(Some((playlist.tracks.items.len(), total)));
+                let offset = playlist.tracks.items.len() as u32;
+                let page = session
+                    .request(&spotify, || {
+                        limiter.run(
+                            Priority::High,
+                            spotify.playlist_items_manual(
+                                id.clone(),
+                                None,
+                                None,
+                                Some(TRACKS_PAGE_SIZE),
+                                Some(offset),
+                            ),
+                        )
+                    })
+                    .await?;
+                // Tracks removed while loading would otherwise keep this going forever.
+                if page.items.is_empty() {
+                    break;
+                }
+                playlist.tracks.items.extend(page.items);
+            }
+            set_progress(None);
 
-        data.sort_unstable_by(|a, b| a.0.total_duration.cmp(&b.0.total_duration));
+            let mut users = HashSet::new();
+
+            for t in &playlist.tracks.items {
+                if let Some(added_by) = &t.added_by {
+                    users.insert(added_by.id.clone());
+                }
+            }
 
-        let mut tracks = Vec::new();
-        let mut users = Vec::new();
-        for (user, mut user_tracks) in data {
-            tracks.append(&mut user_tracks);
-            users.push(user);
+            set_user_progress(Some((0, users.len())));
+            let user_names = join_all(users.into_iter().map(|user_id| {
+                let spotify = spotify.clone();
+                let limiter = limiter.clone();
+                async move {
+                    let key = Borrow::<str>::borrow(&user_id).to_owned();
+                    let cached = user_cache.with_untracked(|cache| {
+                        cache
+                            .get(&key)
+                            .filter(|cached| cached.is_fresh())
+                            .map(|cached| UserLookup::Found {
+                                name: cached.name.clone(),
+                                avatar: cached.avatar.clone(),
+                            })
+                    });
+                    let lookup = match cached {
+                        Some(lookup) => lookup,
+                        None => match session
+                            .request(&spotify, || {
+                                limiter.run(Priority::Low, spotify.user(user_id.clone()))
+                            })
+                            .await
+                        {
+                            Ok(user) => {
+                                let name = api::display_name(user.display_name, &user.id);
+                                let avatar =
+                                    image_url(&user.images, AVATAR_SIZE).map(str::to_owned);
+                                set_user_cache.update(|cache| {
+                                    cache.retain(|_, cached| cached.is_fresh());
+                                    cache.insert(
+                                        key,
+                                        CachedName {
+                                            name: name.clone(),
+                                            avatar: avatar.clone(),
+                                            looked_up: Utc::now(),
+                                        },
+                                    );
+                                });
+                                UserLookup::Found { name, avatar }
+                            }
+                            Err(err) if api::status_code(&err) == Some(404) => UserLookup::Deleted,
+                            Err(_) => UserLookup::Failed,
+                        },
+                    };
+                    // Cached users count too, or the progress would never reach the total.
+                    set_user_progress.update(|progress| {
+                        if let Some((done, _)) = progress {
+                            *done += 1;
+                        }
+                    });
+                    (user_id, lookup)
+                }
+            }))
+            .await
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+            set_user_progress(None);
+            Ok::<_, ApiError>((playlist, user_names))
         }
+    });
 
-        Some(PlaylistInfo {
-            name,
-            total_duration,
-            tracks,
-            users,
+    // Outside of the `Suspense`, so there is a way back while the playlist is still loading. The
+    // query goes along, it holds the filter of the playlists the user came from.
+    let query = use_query_map();
+    let back = move || format!("{BASE_PATH}{}", query.with(|query| query.to_query_string()));
+    let go_back = {
+        let navigate = use_navigate();
+        move |ev: MouseEvent| {
+            // Like `A`, other clicks are left to the browser, like opening it in a new tab.
+            if ev.button() != 0 || ev.ctrl_key() || ev.meta_key() || ev.shift_key() {
+                return;
+            }
+            ev.prevent_default();
+            navigate(&back(), NavigateOptions::default());
+        }
+    };
+    let breadcrumb_name = move || {
+        raw_data.with(|data| match data {
+            Some(Ok((playlist, _))) => playlist.name.clone(),
+            _ => "…".to_owned(),
         })
     };
 
     view! {
-        <Suspense fallback=|| {
-            view! {
-                <h2>Loading playlist</h2>
-                <A href="/collab-playlist/login">If this is taking a long time, click here.</A>
-            }
+        <Show when=move || !embed()>
+            <nav class="breadcrumb">
+                <a href=back on:click=go_back.clone()>
+                    {move || i18n.t(Msg::YourPlaylists)}
+                </a>
+                " / "
+                <span>{breadcrumb_name}</span>
+            </nav>
+        </Show>
+        <Suspense fallback=move || {
+            (!embed())
+                .then(|| {
+                    view! {
+                        <h2>{move || i18n.t(Msg::LoadingPlaylist)}</h2>
+                        {move || {
+                            progress()
+                                .map(|(loaded, total)| {
+                                    view! {
+                                        <p class="loading-progress">
+                                            <progress
+                                                max=total.to_string()
+                                                value=loaded.to_string()
+                                            ></progress>
+                                            {i18n.fill(Msg::LoadingTracks, &[&loaded, &total])}
+                                        </p>
+                                    }
+                                })
+                        }}
+                        {move || {
+                            user_progress()
+                                .map(|(done, total)| {
+                                    view! {
+                                        <p class="loading-progress">
+                                            <progress
+                                                max=total.to_string()
+                                                value=done.to_string()
+                                            ></progress>
+                                            {i18n.fill(Msg::LoadingUsers, &[&done, &total])}
+                                        </p>
+                                    }
+                                })
+                        }}
+                        <A href=app_path("/login")>{move || i18n.t(Msg::TakingLong)}</A>
+                    }
+                })
         }>
+            <ErrorBoundary fallback=move |errors| {
+                view! { <ErrorCard errors=errors retry=move |_| raw_data.refetch()/> }
+            }>
+                {move || {
+                    raw_data
+                        .get()
+                        .map(|data| {
+                            data.map(|(playlist, user_names)| {
+                                view! { <PlaylistView playlist=playlist user_names=user_names/> }
+                            })
+                        })
+                }}
+
+            </ErrorBoundary>
+        </Suspense>
+        <Outlet/>
+    }
+}
+
+#[component]
+fn PlaylistView(
+    playlist: FullPlaylist,
+    user_names: HashMap<UserId<'static>, UserLookup>,
+) -> impl IntoView {
+    let embed = use_embed();
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+
+    let playlist_id = Borrow::<str>::borrow(&playlist.id).to_owned();
+    let name = playlist.name.clone();
+    let heading = move || i18n.fill(Msg::PlaylistTitle, &[&name]);
+    let snapshot = Snapshot::new(&playlist, &user_names);
+    let info = create_memo(move |_| {
+        settings.with(|settings| build_playlist_info(&playlist, &user_names, settings))
+    });
+
+    view! {
+        <Show when=move || !embed()>
+            <h2>{heading.clone()}</h2>
+            <ShareQr/>
+            <ExportButtons playlist=info/>
+            <SettingsPanel/>
+            <SnapshotDiff playlist_id=playlist_id.clone() current=snapshot.clone()/>
+        </Show>
+        <PlaylistBody playlist=info/>
+    }
+}
+
+/// Everything shown about a playlist that only needs its [`PlaylistInfo`], shared by fetched and
+/// imported playlists.
+#[component]
+pub fn PlaylistBody(#[prop(into)] playlist: Signal<PlaylistInfo>) -> impl IntoView {
+    let embed = use_embed();
+    let i18n = use_i18n();
+    let current_user = expect_context::<CurrentUserResource>();
+    let settings = expect_context::<Signal<Settings>>();
+    let highlighted = create_rw_signal(None);
+    let hovered = create_rw_signal(None);
+    let focused = create_rw_signal(None::<UserKey>);
+    // Only the ribbon is narrowed down, the numbers around it stay about the whole playlist.
+    let ribbon = create_memo(move |_| {
+        focused.with(|focused| match focused {
+            Some(user) => playlist.with(|playlist| focus(playlist, user)),
+            None => playlist.get(),
+        })
+    });
+    let focused_name = move || {
+        let user = focused.get()?;
+        playlist.with(|playlist| {
+            playlist
+                .users
+                .iter()
+                .find(|u| u.id == user)
+                .map(|u| u.name.clone())
+        })
+    };
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+    // A memo, so other settings changing doesn't rebuild the view and cut its transitions short.
+    let ribbon_view = create_memo(move |_| settings.with(|s| s.ribbon_view));
+    let (jump_to, set_jump_to) = create_signal(None);
+
+    // The connected user, if they added anything to this playlist.
+    let mine = move || {
+        let key = Some(current_user.get().flatten()?.id);
+        playlist
+            .with(|playlist| playlist.users.iter().any(|user| user.id == key))
+            .then_some(key)
+    };
+    let highlight_mine = move |_| {
+        let Some(key) = mine() else {
+            return;
+        };
+        if highlighted.get_untracked().as_ref() == Some(&key) {
+            highlighted.set(None);
+        } else {
+            highlighted.set(Some(key.clone()));
+            set_jump_to.set(Some(key));
+        }
+    };
+
+    // Nothing to size the ribbon by, an empty playlist or one with only separated podcast episodes.
+    let empty = move || {
+        playlist.with(|playlist| playlist.users.is_empty() || playlist.total_duration.is_zero())
+    };
+
+    view! {
+        <Show
+            when=move || !empty()
+            fallback=move || view! { <p class="playlist-empty">{move || i18n.t(Msg::NoPlayableTracks)}</p> }
+        >
+            <Show when=move || !embed()>
+                <Stats playlist=playlist/>
+                <Summary playlist=playlist highlighted=highlighted jump_to=set_jump_to/>
+                <Show when=move || mine().is_some()>
+                    <button class="highlight-mine" on:click=highlight_mine>
+                        {move || i18n.t(Msg::HighlightMine)}
+                    </button>
+                </Show>
+                // A way to each of the other views.
+                {[
+                    (RibbonView::Tracks, Msg::ShowRibbon),
+                    (RibbonView::StackedBar, Msg::ShowStackedBar),
+                    (RibbonView::Timeline, Msg::ShowTimeline),
+                ]
+                    .into_iter()
+                    .map(|(target, msg)| {
+                        view! {
+                            <Show when=move || ribbon_view() != target>
+                                <button
+                                    class="toggle-view"
+                                    on:click=move |_| set_settings.update(|s| s.ribbon_view = target)
+                                >
+                                    {move || i18n.t(msg)}
+                                </button>
+                            </Show>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+            </Show>
+            {move || match ribbon_view() {
+                RibbonView::Tracks => {
+                    view! {
+                        <Ribbon
+                            playlist=ribbon
+                            highlighted=highlighted
+                            hovered=hovered
+                            focused=focused
+                            jump_to=jump_to
+                        />
+                    }
+                        .into_view()
+                }
+                RibbonView::StackedBar => {
+                    view! { <CompactRibbon playlist=ribbon highlighted=highlighted hovered=hovered/> }
+                        .into_view()
+                }
+                RibbonView::Timeline => {
+                    view! { <Timeline playlist=ribbon highlighted=highlighted hovered=hovered/> }
+                        .into_view()
+                }
+            }}
             {move || {
-                data()
-                    .map(|playlist| {
+                focused_name()
+                    .map(|name| {
                         view! {
-                            <h2>{format!("Playlist: \"{}\":", playlist.name)}</h2>
-                            <table class="ribon-table">
-                                <colgroup>
-                                    {playlist
-                                        .tracks
-                                        .iter()
-                                        .map(|track| {
-                                            let width = format!("{}%", track.relative_size * 100.0);
-                                            view! { <col style:width=width/> }
-                                        })
-                                        .collect::<Vec<_>>()}
-                                </colgroup>
-                                <tr>
-                                    {playlist
-                                        .users
-                                        .into_iter()
-                                        .map(|user| {
-                                            let color = user.color.to_string();
-                                            view! {
-                                                <th
-                                                    style=("--color", color)
-                                                    colspan=user.amount_of_tracks.to_string()
-                                                >
-                                                    <div class="ribon-user-cell">
-                                                        <span class="ribon-user-name">{user.name}</span>
-                                                        <span class="ribon-user-time">
-                                                            {format!(
-                                                                "{} ({:.1}%)",
-                                                                display_duration(&user.total_duration),
-                                                                user.relative_size * 100.0,
-                                                            )}
-
-                                                        </span>
-                                                    </div>
-                                                </th>
-                                            }
-                                        })
-                                        .collect::<Vec<_>>()}
-                                </tr>
-                                <tr class="ribon-track-row">
-                                    {playlist
-                                        .tracks
-                                        .iter()
-                                        .map(|track| {
-                                            let color = track.color.to_string();
-                                            let age = format!("{}%", track.age / 2.0 * 100.0);
-                                            view! {
-                                                <th
-                                                    style=("--color", color)
-                                                    style=("--age", age)
-                                                    class="ribon-track-cell"
-                                                    title=track.name.clone()
-                                                >
-                                                    {if track.age > 0.99 {
-                                                        Some(
-                                                            view! {
-                                                                <img
-                                                                    class="ribon-track-cobweb ribon-track-cobweb-top"
-                                                                    src="/collab-playlist/cobweb-top.png"
-                                                                />
-                                                            },
-                                                        )
-                                                    } else {
-                                                        None
-                                                    }}
-
-                                                    <div class="ribon-track-name">{track.name.clone()}</div>
-                                                    {if track.age > 0.99 {
-                                                        Some(
-                                                            view! {
-                                                                <img class="ribon-track-cobweb" src="/collab-playlist/cobweb.png"/>
-                                                            },
-                                                        )
-                                                    } else {
-                                                        None
-                                                    }}
-
-                                                </th>
-                                            }
-                                        })
-                                        .collect::<Vec<_>>()}
-                                </tr>
-                            </table>
+                            <button class="clear-focus" on:click=move |_| focused.set(None)>
+                                {i18n.fill(Msg::ClearFocus, &[&name])}
+                            </button>
                         }
                     })
             }}
+            <Show when=move || !embed()>
+                <UserTable playlist=playlist/>
+                <Legend playlist=playlist hovered=hovered/>
+                <TrackTable playlist=playlist/>
+                <BarChartRace playlist=playlist/>
+            </Show>
+        </Show>
+    }
+}
 
-        </Suspense>
-        <Outlet/>
+/// Shown for any path that none of the other routes match.
+#[component]
+pub fn NotFound() -> impl IntoView {
+    let i18n = use_i18n();
+
+    view! {
+        <div class="not-found">
+            <h2>{move || i18n.t(Msg::PageNotFound)}</h2>
+            <A href=BASE_PATH>{move || i18n.t(Msg::BackToPlaylists)}</A>
+        </div>
     }
 }
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use leptos::{
+    component, expect_context, view, IntoView, Signal, SignalSet, SignalUpdate, SignalWith,
+    WriteSignal,
+};
+use leptos_use::{storage::use_session_storage, utils::JsonCodec};
+
+use crate::i18n::{use_i18n, Msg};
+
+/// How many auth events are kept, older ones are dropped.
+const MAX_EVENTS: usize = 20;
+
+/// Something that happened while connecting to Spotify.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AuthEvent {
+    pub at: DateTime<Utc>,
+    /// Where in the flow this happened.
+    pub step: String,
+    pub error: Option<String>,
+}
+
+/// The recent auth events, kept in session storage so they survive the redirects to and from
+/// Spotify.
+#[derive(Debug, Clone, Copy)]
+pub struct Diagnostics {
+    events: Signal<Vec<AuthEvent>>,
+    set_events: WriteSignal<Vec<AuthEvent>>,
+}
+
+impl Diagnostics {
+    pub fn from_session_storage() -> Self {
+        let (events, set_events, _) =
+            use_session_storage::<Vec<AuthEvent>, JsonCodec>("auth-events");
+        Self { events, set_events }
+    }
+
+    pub fn record(self, step: impl Into<String>, error: Option<String>) {
+        let event = AuthEvent {
+            at: Utc::now(),
+            step: step.into(),
+            error,
+        };
+        self.set_events.update(|events| {
+            events.push(event);
+            if events.len() > MAX_EVENTS {
+                events.drain(..events.len() - MAX_EVENTS);
+            }
+        });
+    }
+}
+
+pub fn use_diagnostics() -> Diagnostics {
+    expect_context::<Diagnostics>()
+}
+
+/// Lists the recorded auth events, collapsed by default. Meant to be copied into bug reports.
+#[component]
+pub fn DiagnosticsPanel() -> impl IntoView {
+    let i18n = use_i18n();
+    let diagnostics = use_diagnostics();
+
+    let events = move || {
+        diagnostics.events.with(|events| {
+            events
+                .iter()
+                .map(|event| {
+                    view! {
+                        <li>
+                            <code>{event.at.to_rfc3339()}</code>
+                            " "
+                            {event.step.clone()}
+                            {event.error.clone().map(|error| view! { <pre>{error}</pre> })}
+                        </li>
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    view! {
+        <details class="diagnostics">
+            <summary>{move || i18n.t(Msg::Diagnostics)}</summary>
+            <ul>{events}</ul>
+            <button on:click=move |_| diagnostics.set_events.set(Vec::new())>
+                {move || i18n.t(Msg::ClearDiagnostics)}
+            </button>
+        </details>
+    }
+}
@@ -0,0 +1,47 @@
+use leptos::{component, view, Callback, Errors, IntoView, RwSignal, SignalWith};
+
+use crate::{
+    api::ApiError,
+    i18n::{use_i18n, Msg},
+};
+
+/// Fallback of the `ErrorBoundary`s around the resources, lists what went wrong and offers to load
+/// it again.
+#[component]
+pub fn ErrorCard(errors: RwSignal<Errors>, #[prop(into)] retry: Callback<()>) -> impl IntoView {
+    let i18n = use_i18n();
+
+    view! {
+        <div class="error-card">
+            <p>{move || i18n.t(Msg::SomethingWentWrong)}</p>
+            <ul>
+                {move || {
+                    errors
+                        .with(|errors| {
+                            errors
+                                .iter()
+                                .map(|(_, error)| {
+                                    let hint = error
+                                        .downcast_ref::<ApiError>()
+                                        .and_then(|error| error.hint)
+                                        .map(|hint| {
+                                            view! {
+                                                <p class="error-hint">{move || i18n.t(hint)}</p>
+                                            }
+                                        });
+                                    view! {
+                                        <li>
+                                            {error.to_string()}
+                                            {hint}
+                                        </li>
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                }}
+
+            </ul>
+            <button on:click=move |_| retry(())>{move || i18n.t(Msg::Retry)}</button>
+        </div>
+    }
+}
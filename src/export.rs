@@ -0,0 +1,464 @@
+use leptos::{
+    component, create_rw_signal, event_target, expect_context, html, spawn_local, view,
+    wasm_bindgen::JsValue, IntoView, Show, Signal, SignalSet, SignalWith,
+};
+use leptos_router::A;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, BlobPropertyBag, HtmlInputElement, Url};
+
+use crate::{
+    app::PlaylistBody,
+    i18n::{use_i18n, Msg},
+    info::{aged, album_runs, hex, mix, text_color, PlaylistInfo, WHITE},
+    settings::{RibbonOrder, Settings, SettingsPanel},
+    BASE_PATH,
+};
+
+/// Bumped whenever [`PlaylistInfo`] changes in a way older exports can't be read as.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A playlist as saved to, and read back from, a JSON file.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct PlaylistExport {
+    pub schema_version: u32,
+    pub playlist: PlaylistInfo,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    Unreadable,
+    InvalidJson(String),
+    UnsupportedVersion(u32),
+}
+
+/// Reads an export, checking the version before the rest so a newer export gives a clear error
+/// instead of whatever field happened to change.
+pub fn parse_export(json: &str) -> Result<PlaylistInfo, ImportError> {
+    #[derive(serde::Deserialize)]
+    struct Version {
+        schema_version: u32,
+    }
+
+    let Version { schema_version } =
+        serde_json::from_str(json).map_err(|err| ImportError::InvalidJson(err.to_string()))?;
+    if schema_version != SCHEMA_VERSION {
+        return Err(ImportError::UnsupportedVersion(schema_version));
+    }
+
+    serde_json::from_str::<PlaylistExport>(json)
+        .map(|export| export.playlist)
+        .map_err(|err| ImportError::InvalidJson(err.to_string()))
+}
+
+/// A file name for an export of the playlist, without the characters some systems don't allow.
+fn file_name(playlist: &str, extension: &str) -> String {
+    let name = playlist
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let name = name.trim();
+    let name = if name.is_empty() { "playlist" } else { name };
+    format!("{name}.{extension}")
+}
+
+/// Has the browser download `contents` as a file, through a blob url that is revoked right after.
+fn download(file_name: &str, mime: &str, contents: &str) {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let mut options = BlobPropertyBag::new();
+    options.type_(mime);
+    let Ok(blob) = Blob::new_with_str_sequence_and_options(&parts, &options) else {
+        return;
+    };
+    let Ok(url) = Url::create_object_url_with_blob(&blob) else {
+        return;
+    };
+    let anchor = html::a();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    let _ = Url::revoke_object_url(&url);
+}
+
+/// Quotes a CSV field when it has to be, doubling the quotes in it.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// One row per track, in ribbon order, for spreadsheets.
+fn playlist_csv(playlist: &PlaylistInfo) -> String {
+    let mut csv = String::from("track,artists,duration_seconds,contributor,added_at,age\r\n");
+    for track in &playlist.tracks {
+        let contributor = playlist
+            .users
+            .iter()
+            .find(|user| user.id == track.user)
+            .map(|user| user.name.as_str())
+            .unwrap_or_default();
+        let added_at = track
+            .added_at
+            .map(|added_at| added_at.date_naive().to_string())
+            .unwrap_or_default();
+        let row = [
+            csv_field(&track.name),
+            csv_field(&track.artists.join(", ")),
+            track.duration.num_seconds().to_string(),
+            csv_field(contributor),
+            added_at,
+            track.age.to_string(),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Width in pixels the image gets per track, so big playlists get a wide image instead of
+/// unreadable slivers.
+const SVG_TRACK_WIDTH: f64 = 14.0;
+const SVG_MIN_WIDTH: f64 = 1200.0;
+const SVG_HEADER_HEIGHT: f64 = 60.0;
+const SVG_TRACK_HEIGHT: f64 = 240.0;
+const SVG_ALBUM_HEIGHT: f64 = 20.0;
+/// Narrower cells get no text, like the collapsed headers of the ribbon.
+const SVG_MIN_LABEL_WIDTH: f64 = 40.0;
+const SVG_MIN_TRACK_LABEL_WIDTH: f64 = 12.0;
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The ribbon as a standalone image, colors inlined so it looks the same outside of the app.
+fn ribbon_svg(playlist: &PlaylistInfo, settings: &Settings) -> String {
+    let width = (playlist.tracks.len() as f64 * SVG_TRACK_WIDTH).max(SVG_MIN_WIDTH);
+    // Without grouping there are no users to put above the tracks.
+    let header = settings.ribbon_order == RibbonOrder::Contributor;
+    let top = if header { SVG_HEADER_HEIGHT } else { 0.0 };
+    let albums = settings.group_albums;
+    let height = top + SVG_TRACK_HEIGHT + if albums { SVG_ALBUM_HEIGHT } else { 0.0 };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\">"
+    );
+    svg.push_str(&format!(
+        "<rect width=\"{width}\" height=\"{height}\" fill=\"white\"/>"
+    ));
+
+    if header {
+        let mut x = 0.0;
+        for user in &playlist.users {
+            let w = user.relative_size * width;
+            // Half as strong as the tracks, like the headers of the ribbon fade out.
+            let color = mix(user.color, WHITE, 0.5);
+            svg.push_str(&format!(
+                "<rect x=\"{x}\" y=\"0\" width=\"{w}\" height=\"{SVG_HEADER_HEIGHT}\" \
+                 fill=\"{}\"/>",
+                hex(color),
+            ));
+            if w >= SVG_MIN_LABEL_WIDTH {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"14\" text-anchor=\"middle\" \
+                     fill=\"{}\">{}</text>",
+                    x + w / 2.0,
+                    SVG_HEADER_HEIGHT - 10.0,
+                    text_color(color),
+                    xml_escape(&user.name),
+                ));
+            }
+            x += w;
+        }
+    }
+
+    // Neighbouring albums alternate between plain and tinted, with one label under each of them.
+    let runs = album_runs(&playlist.tracks);
+    let bottom = top + SVG_TRACK_HEIGHT;
+    let mut group = 0;
+    let mut x = 0.0;
+    for (i, (track, run)) in playlist.tracks.iter().zip(runs).enumerate() {
+        let w = track.relative_size * width;
+        let color = aged(track.color, track.age, settings.fresh_emphasis);
+        svg.push_str(&format!(
+            "<rect x=\"{x}\" y=\"{top}\" width=\"{w}\" height=\"{SVG_TRACK_HEIGHT}\" \
+             fill=\"{}\" stroke=\"white\" stroke-width=\"0.5\"/>",
+            hex(color),
+        ));
+        if albums && run > 0 {
+            group += 1;
+            let run_width = playlist.tracks[i..i + run]
+                .iter()
+                .map(|track| track.relative_size * width)
+                .sum::<f64>();
+            if group % 2 == 0 {
+                svg.push_str(&format!(
+                    "<rect x=\"{x}\" y=\"{bottom}\" width=\"{run_width}\" \
+                     height=\"{SVG_ALBUM_HEIGHT}\" fill=\"black\" fill-opacity=\"0.05\"/>"
+                ));
+            }
+            if let Some(album) = track
+                .album
+                .as_ref()
+                .filter(|_| run_width >= SVG_MIN_LABEL_WIDTH)
+            {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"11\" font-style=\"italic\" \
+                     text-anchor=\"middle\">{}</text>",
+                    x + run_width / 2.0,
+                    bottom + SVG_ALBUM_HEIGHT - 6.0,
+                    xml_escape(album),
+                ));
+            }
+        }
+        if albums && group % 2 == 0 {
+            svg.push_str(&format!(
+                "<rect x=\"{x}\" y=\"{top}\" width=\"{w}\" height=\"{SVG_TRACK_HEIGHT}\" \
+                 fill=\"black\" fill-opacity=\"0.1\"/>"
+            ));
+        }
+        if w >= SVG_MIN_TRACK_LABEL_WIDTH {
+            // Written bottom to top, so long names fit in narrow cells.
+            let cx = x + w / 2.0;
+            let cy = top + SVG_TRACK_HEIGHT - 8.0;
+            svg.push_str(&format!(
+                "<text x=\"{cx}\" y=\"{cy}\" font-size=\"11\" fill=\"{}\" \
+                 transform=\"rotate(-90 {cx} {cy})\" dominant-baseline=\"middle\">{}</text>",
+                text_color(color),
+                xml_escape(&track.name),
+            ));
+        }
+        x += w;
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Buttons that download the playlist, the JSON in the format [`Import`] reads back.
+#[component]
+pub fn ExportButtons(#[prop(into)] playlist: Signal<PlaylistInfo>) -> impl IntoView {
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+
+    let export_json = move |_| {
+        playlist.with(|playlist| {
+            let export = PlaylistExport {
+                schema_version: SCHEMA_VERSION,
+                playlist: playlist.clone(),
+            };
+            if let Ok(json) = serde_json::to_string_pretty(&export) {
+                download(
+                    &file_name(&playlist.name, "json"),
+                    "application/json",
+                    &json,
+                );
+            }
+        })
+    };
+
+    let export_csv = move |_| {
+        playlist.with(|playlist| {
+            download(
+                &file_name(&playlist.name, "csv"),
+                "text/csv",
+                &playlist_csv(playlist),
+            );
+        })
+    };
+
+    let export_image = move |_| {
+        let svg =
+            settings.with(|settings| playlist.with(|playlist| ribbon_svg(playlist, settings)));
+        let name = playlist.with(|playlist| file_name(&playlist.name, "svg"));
+        download(&name, "image/svg+xml", &svg);
+    };
+
+    view! {
+        <div class="export">
+            <button on:click=export_json>{move || i18n.t(Msg::ExportJson)}</button>
+            <button on:click=export_csv>{move || i18n.t(Msg::ExportCsv)}</button>
+            <button on:click=export_image>{move || i18n.t(Msg::ExportImage)}</button>
+        </div>
+    }
+}
+
+/// Shows a previously exported playlist without talking to Spotify, so it also works for people
+/// that never connected.
+#[component]
+pub fn Import() -> impl IntoView {
+    let i18n = use_i18n();
+    let imported = create_rw_signal(None::<Result<PlaylistInfo, ImportError>>);
+
+    let on_change = move |ev| {
+        let input = event_target::<HtmlInputElement>(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        spawn_local(async move {
+            let text = JsFuture::from(file.text())
+                .await
+                .ok()
+                .and_then(|text| text.as_string());
+            imported.set(Some(match text {
+                Some(text) => parse_export(&text),
+                None => Err(ImportError::Unreadable),
+            }));
+        });
+    };
+
+    let playlist = move || imported.with(|imported| imported.clone()?.ok());
+
+    view! {
+        <div class="import">
+            <label>
+                {move || i18n.t(Msg::ImportJson)}
+                <input type="file" accept="application/json,.json" on:change=on_change/>
+            </label>
+            {move || {
+                imported
+                    .with(|imported| match imported {
+                        Some(Err(err)) => {
+                            let message = match err {
+                                ImportError::Unreadable => i18n.t(Msg::ImportUnreadable).to_owned(),
+                                ImportError::InvalidJson(err) => {
+                                    i18n.fill(Msg::ImportInvalid, &[err])
+                                }
+                                ImportError::UnsupportedVersion(version) => {
+                                    i18n.fill(Msg::ImportUnsupportedVersion, &[version])
+                                }
+                            };
+                            Some(view! { <p class="import-error">{message}</p> })
+                        }
+                        _ => None,
+                    })
+            }}
+
+            <A href=BASE_PATH>{move || i18n.t(Msg::BackToPlaylists)}</A>
+        </div>
+        <Show when=move || playlist().is_some()>
+            <h2>
+                {move || {
+                    i18n.fill(Msg::PlaylistTitle, &[&playlist().unwrap_or_default().name])
+                }}
+            </h2>
+            <SettingsPanel/>
+            <PlaylistBody playlist=Signal::derive(move || playlist().unwrap_or_default())/>
+        </Show>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use rgb::RGB8;
+    use serde_json::json;
+
+    use super::*;
+    use crate::info::{TrackInfo, UserInfo};
+
+    fn playlist() -> PlaylistInfo {
+        PlaylistInfo {
+            name: "Playlist".to_owned(),
+            total_duration: TimeDelta::milliseconds(183_500),
+            tracks: vec![TrackInfo {
+                name: "Track, with a comma".to_owned(),
+                artists: vec!["A".to_owned(), "B".to_owned()],
+                duration: TimeDelta::milliseconds(183_500),
+                relative_size: 1.0,
+                color: RGB8::new(0x12, 0xab, 0xff),
+                ..TrackInfo::default()
+            }],
+            users: vec![UserInfo {
+                name: "Unknown".to_owned(),
+                relative_size: 1.0,
+                total_duration: TimeDelta::milliseconds(183_500),
+                amount_of_tracks: 1,
+                color: RGB8::new(0x80, 0x80, 0x80),
+                ..UserInfo::default()
+            }],
+            ..PlaylistInfo::default()
+        }
+    }
+
+    #[test]
+    fn json_has_seconds_and_hex_colors() {
+        let export = PlaylistExport {
+            schema_version: SCHEMA_VERSION,
+            playlist: playlist(),
+        };
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["schema_version"], json!(1));
+        assert_eq!(json["playlist"]["total_duration"], json!(183.5));
+        assert_eq!(json["playlist"]["tracks"][0]["duration"], json!(183.5));
+        assert_eq!(json["playlist"]["tracks"][0]["color"], json!("#12abff"));
+        assert_eq!(json["playlist"]["users"][0]["total_duration"], json!(183.5));
+        assert_eq!(json["playlist"]["users"][0]["color"], json!("#808080"));
+    }
+
+    #[test]
+    fn json_reads_back() {
+        let export = PlaylistExport {
+            schema_version: SCHEMA_VERSION,
+            playlist: playlist(),
+        };
+        let json = serde_json::to_string(&export).unwrap();
+        assert_eq!(parse_export(&json), Ok(playlist()));
+    }
+
+    #[test]
+    fn json_of_another_version_is_refused() {
+        let json = json!({ "schema_version": SCHEMA_VERSION + 1 }).to_string();
+        assert_eq!(
+            parse_export(&json),
+            Err(ImportError::UnsupportedVersion(SCHEMA_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn csv_quotes_fields_and_has_whole_seconds() {
+        let csv = playlist_csv(&playlist());
+        let mut lines = csv.split("\r\n");
+        assert_eq!(
+            lines.next(),
+            Some("track,artists,duration_seconds,contributor,added_at,age")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("\"Track, with a comma\",\"A, B\",183,Unknown,,0")
+        );
+    }
+
+    #[test]
+    fn svg_labels_every_album_once() {
+        let track = |album: &str| TrackInfo {
+            album: Some(album.to_owned()),
+            relative_size: 1.0 / 3.0,
+            ..TrackInfo::default()
+        };
+        let playlist = PlaylistInfo {
+            tracks: vec![track("First"), track("First"), track("Second")],
+            ..PlaylistInfo::default()
+        };
+        let settings = Settings {
+            group_albums: true,
+            ..Settings::default()
+        };
+        let svg = ribbon_svg(&playlist, &settings);
+        assert_eq!(svg.matches(">First</text>").count(), 1);
+        assert_eq!(svg.matches(">Second</text>").count(), 1);
+        // Only the second album is tinted, under the track and in the album row.
+        assert_eq!(svg.matches("fill-opacity").count(), 2);
+
+        let svg = ribbon_svg(&playlist, &Settings::default());
+        assert!(!svg.contains("First"));
+    }
+}
@@ -0,0 +1,663 @@
+use std::fmt::{Display, Write};
+
+use chrono::TimeDelta;
+use leptos::{expect_context, Signal, SignalWith};
+
+use crate::{info::format_duration, settings::Settings};
+
+/// Every piece of text shown to the user. A locale is a function mapping each of these to its
+/// translation, see [`english`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Msg {
+    ConnectToSpotify,
+    MissingScopes,
+    CodeSpent,
+    StateMismatch,
+    RedirectNoOrigin,
+    RedirectInsecure,
+    AccessDenied,
+    /// Takes what Spotify said went wrong.
+    SpotifyRefused,
+    TryAgain,
+    YourPlaylists,
+    Loading,
+    LoadingPlaylist,
+    TakingLong,
+    Collaborative,
+    Solo,
+    /// Takes the playlist name.
+    PlaylistTitle,
+    UnknownUser,
+    FailedUser,
+    /// Takes the user id.
+    FormerUser,
+    /// Takes the duration and the percentage.
+    UserTime,
+    /// Takes the minimum, maximum and median age in days.
+    AgeSpread,
+    TopContributor,
+    LongestTrack,
+    ShortestTrack,
+    /// Takes the track name and its duration.
+    SummaryLongest,
+    /// Takes the track name and its duration.
+    SummaryShortest,
+    /// Takes the amount of added and removed tracks.
+    SnapshotChanges,
+    SnapshotUnchanged,
+    /// Takes the track name and who added it.
+    SnapshotAdded,
+    /// Takes the track name and who added it.
+    SnapshotRemoved,
+    ShowQr,
+    HideQr,
+    SomethingWentWrong,
+    Retry,
+    Settings,
+    Language,
+    Animations,
+    HeaderLabel,
+    HeaderLabelDuration,
+    HeaderLabelPercentage,
+    HeaderLabelBoth,
+    DurationStyle,
+    DurationStyleClock,
+    DurationStyleHuman,
+    /// Takes the hours and minutes.
+    HoursMinutes,
+    /// Takes the minutes and seconds.
+    MinutesSeconds,
+    /// Takes the seconds.
+    Seconds,
+    RecentDays,
+    RecentTrack,
+    PodcastEpisode,
+    /// Takes the artist name.
+    FavoriteArtist,
+    /// Takes the album name.
+    FavoriteAlbum,
+    PageNotFound,
+    BackToPlaylists,
+    ViewExport,
+    ImportJson,
+    ImportUnreadable,
+    /// Takes the parse error.
+    ImportInvalid,
+    /// Takes the version of the file.
+    ImportUnsupportedVersion,
+    TrackColoring,
+    TrackColoringUser,
+    TrackColoringPopularity,
+    MostObscure,
+    /// Takes the average popularity.
+    AveragePopularity,
+    ContributionRace,
+    Play,
+    Pause,
+    NoPlaylistAccess,
+    Tracks,
+    TrackName,
+    AddedBy,
+    Duration,
+    HeaderThreshold,
+    AgeDays,
+    Contributor,
+    ShuffleColors,
+    ResetColors,
+    OpenPlaylist,
+    OpenPlaylistPlaceholder,
+    InvalidPlaylistId,
+    FreshEmphasis,
+    /// Takes the track name, how often it was added, by how many people and their names.
+    MostAdded,
+    RibbonSize,
+    RibbonSizeDuration,
+    RibbonSizeRecency,
+    Diagnostics,
+    ClearDiagnostics,
+    /// Takes the amount of tracks.
+    TracksPerMonth,
+    /// Takes the user name.
+    ConnectedAs,
+    Logout,
+    HighlightMine,
+    TimeBuckets,
+    TimeBucketsAuto,
+    TimeBucketsDay,
+    TimeBucketsWeek,
+    TimeBucketsMonth,
+    CopyTrackList,
+    /// Takes the amount of tracks.
+    CopiedTracks,
+    BiggestFirst,
+    MaxContributors,
+    /// Takes the amount of merged users.
+    MergedUsers,
+    /// Takes the amount of days.
+    ActiveDays,
+    SomePlaylistsFailed,
+    /// Takes the date and the amount of tracks.
+    BusiestDay,
+    /// Takes the amount of requests.
+    QueuedRequests,
+    /// Takes the amount of tracks loaded and the total.
+    LoadingTracks,
+    Album,
+    GroupByAlbum,
+    EpisodeStats,
+    EpisodeStatsInclude,
+    EpisodeStatsSeparate,
+    /// Takes the amount of episodes and their total duration.
+    PodcastsSummary,
+    FilterPlaylists,
+    NoMatchingPlaylists,
+    NoPlayableTracks,
+    /// Takes the amount of days.
+    OldAfterDays,
+    RibbonOrder,
+    RibbonOrderContributor,
+    RibbonOrderAdded,
+    MalformedPlaylistId,
+    /// Takes the amount of tracks and the percentage.
+    LegendShare,
+    /// Takes the total duration and the amount of tracks.
+    SegmentTitle,
+    ShowStackedBar,
+    ShowRibbon,
+    ExportJson,
+    ExportCsv,
+    ExportImage,
+    ColorblindPalette,
+    /// Takes the total duration.
+    StatsDuration,
+    /// Takes the amount of tracks.
+    StatsTracks,
+    /// Takes the amount of contributors.
+    StatsContributors,
+    /// Takes the average duration.
+    StatsAverage,
+    /// Takes the amount of duplicates.
+    DuplicateTracks,
+    /// Takes the amount of possible duplicates.
+    PossibleDuplicates,
+    OutlineDuplicates,
+    /// Takes the amount of users looked up and the total.
+    LoadingUsers,
+    /// Takes the name of the user.
+    ClearFocus,
+    TrackColoringAge,
+    AgeScaleFresh,
+    /// Takes the days after which a track counts as old.
+    AgeScaleStale,
+    /// Takes the amount of playlists loaded and the total.
+    LoadMorePlaylists,
+    LoginExpiredHint,
+    ConnectionHint,
+    CollaborativeOnly,
+    /// Takes the amount of playlists hidden.
+    HiddenSoloPlaylists,
+    RibbonView,
+    RibbonViewTracks,
+    RibbonViewStackedBar,
+    RibbonViewTimeline,
+    ShowTimeline,
+    UndatedTracks,
+    RateLimited,
+    JustNow,
+    SessionExpired,
+    RedirectUnregistered,
+}
+
+fn english(msg: Msg) -> &'static str {
+    match msg {
+        Msg::ConnectToSpotify => "Connect to spotify",
+        Msg::MissingScopes => {
+            "This site needs more permissions than Spotify gave it when you last connected. \
+             Please connect again to grant them."
+        }
+        Msg::CodeSpent => "That login was already used up, please connect again",
+        Msg::StateMismatch => "That login wasn't started from this page, please connect again",
+        Msg::AccessDenied => "You declined access to your Spotify account",
+        Msg::SpotifyRefused => "Spotify couldn't log you in: {}",
+        Msg::TryAgain => "Try again",
+        Msg::RedirectNoOrigin => {
+            "This page has no web address Spotify could send you back to, open it from a web \
+             server."
+        }
+        Msg::RedirectInsecure => {
+            "Spotify only sends you back to https addresses, or http on 127.0.0.1. Open this page \
+             over https, or through 127.0.0.1 instead of localhost."
+        }
+        Msg::YourPlaylists => "Your playlists:",
+        Msg::Loading => "Loading",
+        Msg::LoadingPlaylist => "Loading playlist",
+        Msg::TakingLong => "If this is taking a long time, click here.",
+        Msg::Collaborative => "collaborative",
+        Msg::Solo => "solo",
+        Msg::PlaylistTitle => "Playlist: \"{}\":",
+        Msg::UnknownUser => "Original/unknown",
+        Msg::FailedUser => "Unknown contributor",
+        Msg::FormerUser => "Former user ({})",
+        Msg::UserTime => "{} ({}%)",
+        Msg::AgeSpread => "Added between {} and {} days ago, median {} days",
+        Msg::TopContributor => "Top contributor: ",
+        Msg::LongestTrack => "Longest track",
+        Msg::ShortestTrack => "Shortest track",
+        Msg::SummaryLongest => "Longest: {} ({})",
+        Msg::SummaryShortest => "Shortest: {} ({})",
+        Msg::SnapshotChanges => "{} tracks added, {} removed since your last visit",
+        Msg::SnapshotUnchanged => "No changes since your last visit",
+        Msg::SnapshotAdded => "+ {} ({})",
+        Msg::SnapshotRemoved => "- {} ({})",
+        Msg::ShowQr => "Show QR",
+        Msg::HideQr => "Hide QR",
+        Msg::SomethingWentWrong => "Something went wrong:",
+        Msg::Retry => "Retry",
+        Msg::Settings => "Settings",
+        Msg::Language => "Language: ",
+        Msg::Animations => "Animate changes to the ribbon",
+        Msg::HeaderLabel => "Show next to names: ",
+        Msg::HeaderLabelDuration => "Duration",
+        Msg::HeaderLabelPercentage => "Percentage",
+        Msg::HeaderLabelBoth => "Both",
+        Msg::DurationStyle => "Show totals as: ",
+        Msg::DurationStyleClock => "1:23:45",
+        Msg::DurationStyleHuman => "1h 23m",
+        Msg::HoursMinutes => "{}h {}m",
+        Msg::MinutesSeconds => "{}m {}s",
+        Msg::Seconds => "{}s",
+        Msg::RecentDays => "Make tracks added in the last this many days glow: ",
+        Msg::RecentTrack => "Recently added",
+        Msg::PodcastEpisode => "podcast episode",
+        Msg::FavoriteArtist => "mostly adds {}",
+        Msg::FavoriteAlbum => "mostly adds tracks from {}",
+        Msg::PageNotFound => "Page not found",
+        Msg::BackToPlaylists => "Back to your playlists",
+        Msg::ViewExport => "View an exported playlist",
+        Msg::ImportJson => "Import JSON: ",
+        Msg::ImportUnreadable => "Could not read the file.",
+        Msg::ImportInvalid => "This is not a playlist export: {}",
+        Msg::ImportUnsupportedVersion => {
+            "This export has version {}, which this version of the site can't read."
+        }
+        Msg::TrackColoring => "Color tracks by: ",
+        Msg::TrackColoringUser => "Who added them",
+        Msg::TrackColoringPopularity => "Popularity (deep cuts to hits)",
+        Msg::MostObscure => "Most obscure taste: ",
+        Msg::AveragePopularity => "average popularity {}",
+        Msg::ContributionRace => "Contributions over time",
+        Msg::Play => "Play",
+        Msg::Pause => "Pause",
+        Msg::Tracks => "Tracks",
+        Msg::TrackName => "Title",
+        Msg::AddedBy => "Added by",
+        Msg::Duration => "Duration",
+        Msg::AgeDays => "Age (days)",
+        Msg::Contributor => "Contributor",
+        Msg::ShuffleColors => "Shuffle colors",
+        Msg::ResetColors => "Original colors",
+        Msg::OpenPlaylist => "Open",
+        Msg::OpenPlaylistPlaceholder => "Playlist link, uri or id",
+        Msg::InvalidPlaylistId => "That is not a link to a playlist",
+        Msg::Diagnostics => "Connection diagnostics",
+        Msg::TracksPerMonth => "~{} tracks/month",
+        Msg::ConnectedAs => "Connected as {}",
+        Msg::Logout => "Log out",
+        Msg::HighlightMine => "Highlight my tracks",
+        Msg::TimeBuckets => "Group charts over time by: ",
+        Msg::TimeBucketsAuto => "Automatic",
+        Msg::TimeBucketsDay => "Day",
+        Msg::TimeBucketsWeek => "Week",
+        Msg::TimeBucketsMonth => "Month",
+        Msg::CopyTrackList => "Copy track list",
+        Msg::CopiedTracks => "Copied {} tracks",
+        Msg::BiggestFirst => "Biggest contributor first",
+        Msg::MaxContributors => "Merge contributors past this many, 0 never merges",
+        Msg::MergedUsers => "{} others",
+        Msg::ActiveDays => "Tracks added across {} different days",
+        Msg::SomePlaylistsFailed => "Some playlists couldn't be loaded",
+        Msg::BusiestDay => "Busiest day: {} with {} additions",
+        Msg::QueuedRequests => "{} requests waiting",
+        Msg::LoadingTracks => "{} of {} tracks loaded",
+        Msg::Album => "Album",
+        Msg::GroupByAlbum => "Group by album",
+        Msg::EpisodeStats => "Podcast episodes",
+        Msg::EpisodeStatsInclude => "Count them like tracks",
+        Msg::EpisodeStatsSeparate => "Keep them separate",
+        Msg::PodcastsSummary => "Podcasts: {} episodes, {}",
+        Msg::FilterPlaylists => "Filter playlists",
+        Msg::NoMatchingPlaylists => "No playlists match the filter",
+        Msg::NoPlayableTracks => "This playlist has no playable tracks yet",
+        Msg::OldAfterDays => "Tracks are fully aged after {} days",
+        Msg::RibbonOrder => "Order tracks by: ",
+        Msg::RibbonOrderContributor => "Contributor",
+        Msg::RibbonOrderAdded => "Date added",
+        Msg::LegendShare => "{} tracks, {}%",
+        Msg::SegmentTitle => "{} in {} tracks",
+        Msg::ShowStackedBar => "Show as a single bar",
+        Msg::ShowRibbon => "Show every track",
+        Msg::ExportJson => "Export JSON",
+        Msg::ExportCsv => "Download CSV",
+        Msg::ExportImage => "Export image",
+        Msg::ColorblindPalette => "Use colors that colorblind people can tell apart",
+        Msg::StatsDuration => "{} in total",
+        Msg::StatsTracks => "{} tracks",
+        Msg::StatsContributors => "{} contributors",
+        Msg::StatsAverage => "{} per track on average",
+        Msg::DuplicateTracks => "{} duplicate tracks",
+        Msg::PossibleDuplicates => "{} possible duplicates",
+        Msg::OutlineDuplicates => "Outline duplicates",
+        Msg::LoadingUsers => "{} of {} users looked up",
+        Msg::ClearFocus => "Only showing {}, show everyone",
+        Msg::TrackColoringAge => "How long ago they were added",
+        Msg::AgeScaleFresh => "Just added",
+        Msg::AgeScaleStale => "{} days or longer ago",
+        Msg::LoadMorePlaylists => "Load more ({} of {})",
+        Msg::LoginExpiredHint => "Spotify no longer accepts your login, log in again.",
+        Msg::ConnectionHint => "Spotify couldn't be reached, check your connection and try again.",
+        Msg::CollaborativeOnly => "Only collaborative playlists",
+        Msg::HiddenSoloPlaylists => "{} solo playlists hidden",
+        Msg::RibbonView => "Show the playlist as: ",
+        Msg::RibbonViewTracks => "Every track",
+        Msg::RibbonViewStackedBar => "One bar per contributor",
+        Msg::RibbonViewTimeline => "A timeline of when tracks were added",
+        Msg::ShowTimeline => "Show as a timeline",
+        Msg::UndatedTracks => "Undated",
+        Msg::RateLimited => "Spotify asked to slow down, retrying…",
+        Msg::JustNow => "Just now",
+        Msg::SessionExpired => "Your Spotify session ended, please connect again",
+        Msg::RedirectUnregistered => {
+            "Spotify only sends you back to addresses this app was registered with, and this isn't \
+             one of them. Open the page where it's hosted instead."
+        }
+        Msg::MalformedPlaylistId => {
+            "The address doesn't contain a valid playlist id, check the link for typos."
+        }
+        Msg::ClearDiagnostics => "Clear",
+        Msg::RibbonSize => "Size tracks by: ",
+        Msg::RibbonSizeDuration => "Duration",
+        Msg::RibbonSizeRecency => "Recency (newer is wider)",
+        Msg::MostAdded => "\"{}\" was added {} times by {} people ({})",
+        Msg::FreshEmphasis => "Fade out old tracks instead of covering them in cobwebs",
+        Msg::HeaderThreshold => "Hide names of users with less than this percentage: ",
+        Msg::NoPlaylistAccess => {
+            "You don't have access to this playlist, ask an owner to make it collaborative or \
+             public."
+        }
+    }
+}
+
+fn dutch(msg: Msg) -> &'static str {
+    match msg {
+        Msg::ConnectToSpotify => "Verbinden met spotify",
+        Msg::MissingScopes => {
+            "Deze site heeft meer rechten nodig dan Spotify gaf toen je de vorige keer verbond. \
+             Verbind opnieuw om ze te geven."
+        }
+        Msg::CodeSpent => "Die login werd al gebruikt, verbind opnieuw",
+        Msg::StateMismatch => "Die login werd niet op deze pagina gestart, verbind opnieuw",
+        Msg::AccessDenied => "Je weigerde toegang tot je Spotify-account",
+        Msg::SpotifyRefused => "Spotify kon je niet aanmelden: {}",
+        Msg::TryAgain => "Opnieuw proberen",
+        Msg::RedirectNoOrigin => {
+            "Deze pagina heeft geen webadres waar Spotify je naar terug kan sturen, open ze via \
+             een webserver."
+        }
+        Msg::RedirectInsecure => {
+            "Spotify stuurt je enkel terug naar https-adressen, of http op 127.0.0.1. Open deze \
+             pagina via https, of via 127.0.0.1 in plaats van localhost."
+        }
+        Msg::YourPlaylists => "Jouw afspeellijsten:",
+        Msg::Loading => "Laden",
+        Msg::LoadingPlaylist => "Afspeellijst laden",
+        Msg::TakingLong => "Als dit lang duurt, klik hier.",
+        Msg::Collaborative => "gezamenlijk",
+        Msg::Solo => "solo",
+        Msg::PlaylistTitle => "Afspeellijst: \"{}\":",
+        Msg::UnknownUser => "Origineel/onbekend",
+        Msg::FailedUser => "Onbekende bijdrager",
+        Msg::FormerUser => "Voormalige gebruiker ({})",
+        Msg::UserTime => "{} ({}%)",
+        Msg::AgeSpread => "Toegevoegd tussen {} en {} dagen geleden, mediaan {} dagen",
+        Msg::TopContributor => "Grootste bijdrager: ",
+        Msg::LongestTrack => "Langste nummer",
+        Msg::ShortestTrack => "Kortste nummer",
+        Msg::SummaryLongest => "Langste: {} ({})",
+        Msg::SummaryShortest => "Kortste: {} ({})",
+        Msg::SnapshotChanges => "{} nummers toegevoegd, {} verwijderd sinds je vorige bezoek",
+        Msg::SnapshotUnchanged => "Niets veranderd sinds je vorige bezoek",
+        Msg::SnapshotAdded => "+ {} ({})",
+        Msg::SnapshotRemoved => "- {} ({})",
+        Msg::ShowQr => "Toon QR",
+        Msg::HideQr => "Verberg QR",
+        Msg::SomethingWentWrong => "Er ging iets mis:",
+        Msg::Retry => "Opnieuw proberen",
+        Msg::Settings => "Instellingen",
+        Msg::Language => "Taal: ",
+        Msg::Animations => "Wijzigingen aan het lint animeren",
+        Msg::HeaderLabel => "Toon naast namen: ",
+        Msg::HeaderLabelDuration => "Duur",
+        Msg::HeaderLabelPercentage => "Percentage",
+        Msg::HeaderLabelBoth => "Beide",
+        Msg::DurationStyle => "Toon totalen als: ",
+        Msg::DurationStyleClock => "1:23:45",
+        Msg::DurationStyleHuman => "1u 23m",
+        Msg::HoursMinutes => "{}u {}m",
+        Msg::MinutesSeconds => "{}m {}s",
+        Msg::Seconds => "{}s",
+        Msg::RecentDays => "Laat nummers toegevoegd in de laatste zoveel dagen oplichten: ",
+        Msg::RecentTrack => "Recent toegevoegd",
+        Msg::PodcastEpisode => "podcastaflevering",
+        Msg::FavoriteArtist => "voegt vooral {} toe",
+        Msg::FavoriteAlbum => "voegt vooral nummers van {} toe",
+        Msg::PageNotFound => "Pagina niet gevonden",
+        Msg::BackToPlaylists => "Terug naar je afspeellijsten",
+        Msg::ViewExport => "Bekijk een geëxporteerde afspeellijst",
+        Msg::ImportJson => "JSON importeren: ",
+        Msg::ImportUnreadable => "Het bestand kon niet gelezen worden.",
+        Msg::ImportInvalid => "Dit is geen export van een afspeellijst: {}",
+        Msg::ImportUnsupportedVersion => {
+            "Deze export heeft versie {}, die deze versie van de site niet kan lezen."
+        }
+        Msg::TrackColoring => "Kleur nummers volgens: ",
+        Msg::TrackColoringUser => "Wie ze toevoegde",
+        Msg::TrackColoringPopularity => "Populariteit (verborgen parels tot hits)",
+        Msg::MostObscure => "Meest obscure smaak: ",
+        Msg::AveragePopularity => "gemiddelde populariteit {}",
+        Msg::ContributionRace => "Bijdragen doorheen de tijd",
+        Msg::Play => "Afspelen",
+        Msg::Pause => "Pauzeren",
+        Msg::Tracks => "Nummers",
+        Msg::TrackName => "Titel",
+        Msg::AddedBy => "Toegevoegd door",
+        Msg::Duration => "Duur",
+        Msg::AgeDays => "Leeftijd (dagen)",
+        Msg::Contributor => "Bijdrager",
+        Msg::ShuffleColors => "Kleuren schudden",
+        Msg::ResetColors => "Oorspronkelijke kleuren",
+        Msg::OpenPlaylist => "Openen",
+        Msg::OpenPlaylistPlaceholder => "Link, uri of id van een afspeellijst",
+        Msg::InvalidPlaylistId => "Dat is geen link naar een afspeellijst",
+        Msg::Diagnostics => "Verbindingsdiagnose",
+        Msg::TracksPerMonth => "~{} nummers/maand",
+        Msg::ConnectedAs => "Verbonden als {}",
+        Msg::Logout => "Afmelden",
+        Msg::HighlightMine => "Markeer mijn nummers",
+        Msg::TimeBuckets => "Groepeer grafieken doorheen de tijd per: ",
+        Msg::TimeBucketsAuto => "Automatisch",
+        Msg::TimeBucketsDay => "Dag",
+        Msg::TimeBucketsWeek => "Week",
+        Msg::TimeBucketsMonth => "Maand",
+        Msg::CopyTrackList => "Lijst kopiëren",
+        Msg::CopiedTracks => "{} nummers gekopieerd",
+        Msg::BiggestFirst => "Grootste bijdrager eerst",
+        Msg::MaxContributors => "Voeg bijdragers samen voorbij zoveel, 0 voegt nooit samen",
+        Msg::MergedUsers => "{} anderen",
+        Msg::ActiveDays => "Nummers toegevoegd op {} verschillende dagen",
+        Msg::SomePlaylistsFailed => "Sommige afspeellijsten konden niet geladen worden",
+        Msg::BusiestDay => "Drukste dag: {} met {} toevoegingen",
+        Msg::QueuedRequests => "{} verzoeken wachten",
+        Msg::LoadingTracks => "{} van {} nummers geladen",
+        Msg::Album => "Album",
+        Msg::GroupByAlbum => "Groepeer per album",
+        Msg::EpisodeStats => "Podcastafleveringen",
+        Msg::EpisodeStatsInclude => "Tel ze als nummers",
+        Msg::EpisodeStatsSeparate => "Houd ze apart",
+        Msg::PodcastsSummary => "Podcasts: {} afleveringen, {}",
+        Msg::FilterPlaylists => "Afspeellijsten filteren",
+        Msg::NoMatchingPlaylists => "Geen afspeellijsten komen overeen met de filter",
+        Msg::NoPlayableTracks => "Deze afspeellijst heeft nog geen afspeelbare nummers",
+        Msg::OldAfterDays => "Nummers zijn volledig verouderd na {} dagen",
+        Msg::RibbonOrder => "Orden nummers volgens: ",
+        Msg::RibbonOrderContributor => "Bijdrager",
+        Msg::RibbonOrderAdded => "Datum toegevoegd",
+        Msg::LegendShare => "{} nummers, {}%",
+        Msg::SegmentTitle => "{} in {} nummers",
+        Msg::ShowStackedBar => "Toon als één balk",
+        Msg::ShowRibbon => "Toon elk nummer",
+        Msg::ExportJson => "JSON exporteren",
+        Msg::ExportCsv => "CSV downloaden",
+        Msg::ExportImage => "Afbeelding exporteren",
+        Msg::ColorblindPalette => {
+            "Gebruik kleuren die kleurenblinde mensen uit elkaar kunnen houden"
+        }
+        Msg::StatsDuration => "{} in totaal",
+        Msg::StatsTracks => "{} nummers",
+        Msg::StatsContributors => "{} bijdragers",
+        Msg::StatsAverage => "gemiddeld {} per nummer",
+        Msg::DuplicateTracks => "{} dubbele nummers",
+        Msg::PossibleDuplicates => "{} mogelijk dubbele nummers",
+        Msg::OutlineDuplicates => "Dubbele nummers omlijnen",
+        Msg::LoadingUsers => "{} van {} gebruikers opgezocht",
+        Msg::ClearFocus => "Enkel {} wordt getoond, toon iedereen",
+        Msg::TrackColoringAge => "Hoe lang geleden ze werden toegevoegd",
+        Msg::AgeScaleFresh => "Net toegevoegd",
+        Msg::AgeScaleStale => "{} dagen of langer geleden",
+        Msg::LoadMorePlaylists => "Meer laden ({} van {})",
+        Msg::LoginExpiredHint => "Spotify aanvaardt je login niet meer, log opnieuw in.",
+        Msg::ConnectionHint => {
+            "Spotify kon niet bereikt worden, controleer je verbinding en probeer opnieuw."
+        }
+        Msg::CollaborativeOnly => "Enkel gedeelde afspeellijsten",
+        Msg::HiddenSoloPlaylists => "{} solo afspeellijsten verborgen",
+        Msg::RibbonView => "Toon de afspeellijst als: ",
+        Msg::RibbonViewTracks => "Elk nummer",
+        Msg::RibbonViewStackedBar => "Eén balk per bijdrager",
+        Msg::RibbonViewTimeline => "Een tijdlijn van wanneer nummers werden toegevoegd",
+        Msg::ShowTimeline => "Toon als tijdlijn",
+        Msg::UndatedTracks => "Zonder datum",
+        Msg::RateLimited => "Spotify vroeg om trager te gaan, opnieuw proberen…",
+        Msg::JustNow => "Zonet",
+        Msg::SessionExpired => "Je Spotify-sessie is verlopen, verbind opnieuw",
+        Msg::RedirectUnregistered => {
+            "Spotify stuurt je enkel terug naar adressen waarvoor deze app geregistreerd is, en \
+             dit is er geen van. Open de pagina waar ze gehost wordt."
+        }
+        Msg::MalformedPlaylistId => {
+            "Het adres bevat geen geldige afspeellijst-id, controleer de link op typfouten."
+        }
+        Msg::ClearDiagnostics => "Wissen",
+        Msg::RibbonSize => "Grootte van nummers volgens: ",
+        Msg::RibbonSizeDuration => "Duur",
+        Msg::RibbonSizeRecency => "Recentheid (nieuwer is breder)",
+        Msg::MostAdded => "\"{}\" werd {} keer toegevoegd door {} mensen ({})",
+        Msg::FreshEmphasis => {
+            "Laat oude nummers vervagen in plaats van ze met spinnenwebben te bedekken"
+        }
+        Msg::HeaderThreshold => "Verberg namen van gebruikers met minder dan dit percentage: ",
+        Msg::NoPlaylistAccess => {
+            "Je hebt geen toegang tot deze afspeellijst, vraag een eigenaar om ze gezamenlijk of \
+             openbaar te maken."
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum Locale {
+    #[default]
+    English,
+    Dutch,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::Dutch];
+
+    /// The name of the locale, in that locale.
+    pub fn name(self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::Dutch => "Nederlands",
+        }
+    }
+
+    pub fn text(self, msg: Msg) -> &'static str {
+        match self {
+            Locale::English => english(msg),
+            Locale::Dutch => dutch(msg),
+        }
+    }
+
+    /// The text of `msg` with each `{}` replaced by the next argument.
+    pub fn fill(self, msg: Msg, args: &[&dyn Display]) -> String {
+        let mut parts = self.text(msg).split("{}");
+        let mut out = parts.next().unwrap_or_default().to_owned();
+        let mut args = args.iter();
+        for part in parts {
+            if let Some(arg) = args.next() {
+                write!(out, "{arg}").ok();
+            }
+            out.push_str(part);
+        }
+        out
+    }
+
+    fn decimal_separator(self) -> char {
+        match self {
+            Locale::English => '.',
+            Locale::Dutch => ',',
+        }
+    }
+
+    pub fn number(self, value: f64) -> String {
+        value
+            .to_string()
+            .replace('.', &self.decimal_separator().to_string())
+    }
+
+    /// Formats a fraction as a percentage with one decimal, without the `%` sign.
+    pub fn percentage(self, fraction: f64) -> String {
+        format!("{:.1}", fraction * 100.0).replace('.', &self.decimal_separator().to_string())
+    }
+}
+
+/// Translates text for the locale picked in the settings.
+#[derive(Debug, Clone, Copy)]
+pub struct I18n {
+    settings: Signal<Settings>,
+}
+
+pub fn use_i18n() -> I18n {
+    I18n {
+        settings: expect_context::<Signal<Settings>>(),
+    }
+}
+
+impl I18n {
+    pub fn locale(self) -> Locale {
+        self.settings.with(|settings| settings.locale)
+    }
+
+    pub fn t(self, msg: Msg) -> &'static str {
+        self.locale().text(msg)
+    }
+
+    pub fn fill(self, msg: Msg, args: &[&dyn Display]) -> String {
+        self.locale().fill(msg, args)
+    }
+
+    /// Formats a total duration in the style picked in the settings.
+    pub fn duration(self, dur: &TimeDelta) -> String {
+        self.settings
+            .with(|settings| format_duration(dur, settings.duration_style, settings.locale))
+    }
+}
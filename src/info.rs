@@ -0,0 +1,1445 @@
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::Entry, HashMap},
+};
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeDelta, Utc};
+use random_color::RandomColor;
+use rgb::RGB8;
+use rspotify::model::{FullEpisode, FullPlaylist, FullTrack, PlayableItem, TrackId, UserId};
+
+use crate::{
+    i18n::{Locale, Msg},
+    settings::{
+        DurationStyle, EpisodeStats, RibbonOrder, RibbonSize, Settings, TimeBuckets, TrackColoring,
+    },
+};
+
+/// Identifies a contributor, `None` being the tracks that have no `added_by`.
+pub type UserKey = Option<UserId<'static>>;
+
+/// Durations in exports as seconds, with the milliseconds as the fraction.
+mod duration_secs {
+    use chrono::TimeDelta;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(dur: &TimeDelta, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(dur.num_milliseconds() as f64 / 1000.0)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TimeDelta, D::Error> {
+        let seconds = f64::deserialize(deserializer)?;
+        Ok(TimeDelta::milliseconds((seconds * 1000.0).round() as i64))
+    }
+}
+
+/// Colors in exports as `#rrggbb`, see [`hex`].
+mod color_hex {
+    use rgb::RGB8;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &RGB8, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::hex(*color))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RGB8, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex.strip_prefix('#')
+            .filter(|digits| digits.len() == 6)
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .map(super::hex_color)
+            .ok_or_else(|| D::Error::custom(format!("{hex} is not a #rrggbb color")))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+pub struct TrackInfo {
+    /// Position of the track in the playlist, stable regardless of how the ribbon is sorted.
+    pub index: usize,
+    /// `None` for local files.
+    pub id: Option<TrackId<'static>>,
+    pub user: UserKey,
+    pub name: String,
+    /// Exports from before this was added have no artists.
+    #[serde(default)]
+    pub artists: Vec<String>,
+    /// `None` if Spotify doesn't know it, like for most local files.
+    #[serde(default)]
+    pub album: Option<String>,
+    /// A podcast episode rather than a song, see [`Settings::episodes`].
+    #[serde(default)]
+    pub is_episode: bool,
+    /// The page on Spotify, `None` for local files.
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(with = "duration_secs")]
+    pub duration: TimeDelta,
+    pub relative_size: f64,
+    #[serde(with = "color_hex")]
+    pub color: RGB8,
+    pub age: f64,
+    pub extreme: Option<Extreme>,
+    /// Added within the last [`Settings::recent_days`].
+    pub is_recent: bool,
+    /// From 0 to 100, `None` for local files which Spotify doesn't track.
+    pub popularity: Option<u32>,
+    pub added_at: Option<DateTime<Utc>>,
+    /// Days since the track was added, unlike `age` not capped. `None` without `added_at`.
+    pub age_days: Option<i64>,
+    /// Added less than [`JUST_NOW`] ago, or seemingly in the future.
+    #[serde(default)]
+    pub just_now: bool,
+    /// Set on every copy, also the first one.
+    #[serde(default)]
+    pub duplicate: Option<Duplicate>,
+}
+
+/// Why a track counts as being in the playlist more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Duplicate {
+    /// The very same track.
+    Exact,
+    /// Another track with the same title and artists, like the same song on a different album.
+    Possible,
+}
+
+/// The copies in a playlist past the first one of each track, see [`Duplicate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub struct Duplicates {
+    pub exact: usize,
+    pub possible: usize,
+}
+
+/// Marks the longest and shortest track of the playlist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Extreme {
+    Longest,
+    Shortest,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+pub struct UserInfo {
+    pub id: UserKey,
+    pub name: String,
+    pub relative_size: f64,
+    #[serde(with = "duration_secs")]
+    pub total_duration: TimeDelta,
+    pub amount_of_tracks: u64,
+    #[serde(with = "color_hex")]
+    pub color: RGB8,
+    /// `None` if none of the user's tracks have an `added_at`.
+    pub ages: Option<AgeSpread>,
+    pub favorite: Option<Favorite>,
+    /// `None` if none of the user's tracks have a popularity.
+    pub average_popularity: Option<f64>,
+    /// Over the time between their first and last track, `None` with less than two tracks with an
+    /// `added_at`.
+    pub tracks_per_month: Option<f64>,
+    /// Stands for all users past [`Settings::max_contributors`] instead of a single user.
+    #[serde(default)]
+    pub merged: bool,
+    /// Tracks this user added that were already in the playlist, exact and possible duplicates.
+    #[serde(default)]
+    pub duplicates: usize,
+    /// Url of their profile picture, `None` if they have none.
+    #[serde(default)]
+    pub avatar: Option<String>,
+    /// Spotify couldn't be asked who this is, even after retrying, so the name is a placeholder.
+    #[serde(default)]
+    pub lookup_failed: bool,
+}
+
+/// The artist or album a user added the most tracks of.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Favorite {
+    Artist(String),
+    Album(String),
+}
+
+impl Favorite {
+    /// Only counts if a user added more than one track of it. Ties go to whichever came first in
+    /// the playlist, an artist wins from an album with as many tracks since the album is then most
+    /// likely all the artist's tracks.
+    fn new(tracks: &[&FullTrack]) -> Option<Self> {
+        fn most_common<'a>(names: impl Iterator<Item = &'a str>) -> Option<(&'a str, usize)> {
+            let mut counts = Vec::<(&str, usize)>::new();
+            for name in names {
+                match counts.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((name, 1)),
+                }
+            }
+            counts
+                .into_iter()
+                .rev()
+                .max_by_key(|&(_, count)| count)
+                .filter(|&(_, count)| count > 1)
+        }
+
+        let artist = most_common(
+            tracks
+                .iter()
+                .flat_map(|track| track.artists.iter().map(|artist| artist.name.as_str())),
+        );
+        let album = most_common(
+            tracks
+                .iter()
+                .map(|track| track.album.name.as_str())
+                .filter(|name| !name.is_empty()),
+        );
+
+        match (artist, album) {
+            (Some((_, a)), Some((album, b))) if b > a => Some(Self::Album(album.to_owned())),
+            (Some((artist, _)), _) => Some(Self::Artist(artist.to_owned())),
+            (None, Some((album, _))) => Some(Self::Album(album.to_owned())),
+            (None, None) => None,
+        }
+    }
+}
+
+/// How long ago, in days, a user added their tracks.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AgeSpread {
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+}
+
+impl AgeSpread {
+    fn new(ages: impl Iterator<Item = i64>) -> Option<Self> {
+        let mut ages = ages.collect::<Vec<_>>();
+        ages.sort_unstable();
+
+        let min = *ages.first()?;
+        let max = *ages.last()?;
+        let mid = ages.len() / 2;
+        let median = if ages.len() % 2 == 0 {
+            (ages[mid - 1] + ages[mid]) as f64 / 2.0
+        } else {
+            ages[mid] as f64
+        };
+
+        Some(Self {
+            min: min as f64,
+            median,
+            max: max as f64,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+pub struct PlaylistInfo {
+    pub name: String,
+    #[serde(with = "duration_secs")]
+    pub total_duration: TimeDelta,
+
+    pub tracks: Vec<TrackInfo>,
+    pub users: Vec<UserInfo>,
+    /// `None` if no track is in the playlist more than once.
+    pub most_added: Option<MostAdded>,
+    /// Distinct days on which tracks were added, tracks without an `added_at` aside.
+    #[serde(default)]
+    pub active_days: usize,
+    /// The day the most tracks were added, ties go to the earliest. `None` if no track has an
+    /// `added_at`.
+    pub busiest_day: Option<BusiestDay>,
+    /// The episodes kept out of the other numbers, see [`Settings::episodes`]. `None` if they are
+    /// counted as tracks or there are none.
+    pub podcasts: Option<Podcasts>,
+    #[serde(default)]
+    pub duplicates: Duplicates,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Podcasts {
+    pub episodes: usize,
+    #[serde(with = "duration_secs")]
+    pub total_duration: TimeDelta,
+}
+
+/// Something in the playlist that is shown as a track.
+#[derive(Debug, Clone, Copy)]
+enum Item<'a> {
+    Track(&'a FullTrack),
+    /// Only with [`EpisodeStats::Include`].
+    Episode(&'a FullEpisode),
+}
+
+impl<'a> Item<'a> {
+    fn duration(self) -> TimeDelta {
+        match self {
+            Item::Track(track) => track.duration,
+            Item::Episode(episode) => episode.duration,
+        }
+    }
+
+    /// Where Spotify opens it, `None` for local files.
+    fn url(self) -> Option<String> {
+        let external_urls = match self {
+            Item::Track(track) => &track.external_urls,
+            Item::Episode(episode) => &episode.external_urls,
+        };
+        external_urls.get("spotify").cloned()
+    }
+
+    fn track(self) -> Option<&'a FullTrack> {
+        match self {
+            Item::Track(track) => Some(track),
+            Item::Episode(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BusiestDay {
+    pub date: NaiveDate,
+    pub count: usize,
+}
+
+/// The track that is in the playlist the most times.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MostAdded {
+    pub name: String,
+    pub count: usize,
+    /// Everyone that added a copy, once each.
+    pub users: Vec<String>,
+}
+
+impl MostAdded {
+    /// Ties go to the track that comes first in the playlist. Local files have no id and can't be
+    /// told apart, so they are never counted.
+    fn new(tracks: &[TrackInfo], users: &[UserInfo]) -> Option<Self> {
+        let mut copies = HashMap::<&TrackId<'static>, Vec<&TrackInfo>>::new();
+        for track in tracks {
+            if let Some(id) = &track.id {
+                copies.entry(id).or_default().push(track);
+            }
+        }
+
+        let copies = copies
+            .into_values()
+            .filter(|copies| copies.len() > 1)
+            .max_by(|a, b| {
+                let first = |copies: &[&TrackInfo]| copies.iter().map(|t| t.index).min();
+                a.len().cmp(&b.len()).then(first(b).cmp(&first(a)))
+            })?;
+
+        let mut names = Vec::new();
+        for track in &copies {
+            let name = users
+                .iter()
+                .find(|user| user.id == track.user)
+                .map(|user| user.name.clone())
+                .unwrap_or_default();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        Some(Self {
+            name: copies[0].name.clone(),
+            count: copies.len(),
+            users: names,
+        })
+    }
+}
+
+/// A period of time the time based charts group tracks by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucket {
+    Day,
+    /// Starting on monday.
+    Week,
+    Month,
+}
+
+impl Bucket {
+    /// The granularity for [`TimeBuckets::Auto`], picked so a playlist gives a reasonable amount
+    /// of buckets.
+    fn for_span(span: TimeDelta) -> Self {
+        if span <= TimeDelta::days(31) {
+            Bucket::Day
+        } else if span <= TimeDelta::days(366) {
+            Bucket::Week
+        } else {
+            Bucket::Month
+        }
+    }
+
+    /// The first day of the bucket `date` falls in.
+    fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Bucket::Day => date,
+            Bucket::Week => date - TimeDelta::days(date.weekday().num_days_from_monday().into()),
+            Bucket::Month => date.with_day(1).expect("day 1 always exists"),
+        }
+    }
+
+    fn next(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Bucket::Day => start + TimeDelta::days(1),
+            Bucket::Week => start + TimeDelta::weeks(1),
+            Bucket::Month => start
+                .checked_add_months(Months::new(1))
+                .expect("in range of the tracks"),
+        }
+    }
+
+    pub fn label(self, start: NaiveDate) -> String {
+        match self {
+            Bucket::Day | Bucket::Week => start.format("%Y-%m-%d").to_string(),
+            Bucket::Month => start.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// How much each user had added to the playlist by the end of a bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketTotals {
+    /// The first day of the bucket.
+    pub start: NaiveDate,
+    pub totals: HashMap<UserKey, TimeDelta>,
+}
+
+/// The running totals of every user for each bucket from the first to the last track added,
+/// including buckets in which nothing was added. Tracks without an `added_at` are left out.
+pub fn bucket_totals(playlist: &PlaylistInfo, buckets: TimeBuckets) -> (Bucket, Vec<BucketTotals>) {
+    let mut tracks = playlist
+        .tracks
+        .iter()
+        .filter_map(|track| Some((track.added_at?, track)))
+        .collect::<Vec<_>>();
+    tracks.sort_unstable_by_key(|&(added_at, _)| added_at);
+
+    let bucket = match buckets {
+        TimeBuckets::Day => Bucket::Day,
+        TimeBuckets::Week => Bucket::Week,
+        TimeBuckets::Month => Bucket::Month,
+        TimeBuckets::Auto => match (tracks.first(), tracks.last()) {
+            (Some(&(first, _)), Some(&(last, _))) => Bucket::for_span(last - first),
+            _ => Bucket::Month,
+        },
+    };
+
+    let tracks = tracks
+        .into_iter()
+        .map(|(added_at, track)| (bucket.start(added_at.date_naive()), track))
+        .collect::<Vec<_>>();
+    let (Some(&(first, _)), Some(&(last, _))) = (tracks.first(), tracks.last()) else {
+        return (bucket, Vec::new());
+    };
+
+    let mut buckets = Vec::new();
+    let mut totals = HashMap::new();
+    let mut tracks = tracks.into_iter().peekable();
+    let mut start = first;
+    while start <= last {
+        while let Some((_, track)) = tracks.next_if(|&(s, _)| s == start) {
+            *totals
+                .entry(track.user.clone())
+                .or_insert_with(TimeDelta::zero) += track.duration;
+        }
+        buckets.push(BucketTotals {
+            start,
+            totals: totals.clone(),
+        });
+        start = bucket.next(start);
+    }
+    (bucket, buckets)
+}
+
+/// The result of resolving the user that added a track.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserLookup {
+    Found {
+        name: String,
+        avatar: Option<String>,
+    },
+    /// Spotify no longer knows this user, the account was most likely deleted.
+    Deleted,
+    /// Spotify kept failing to answer. The user is still kept apart by their id, so several failed
+    /// users don't end up as one.
+    Failed,
+}
+
+/// Color of the tracks without `added_by`, which are there from before the playlist was
+/// collaborative or came from an import. Without a hue `RandomColor` never generates grays and the
+/// colorblind palette has none either, so this can not be confused with a user.
+const UNKNOWN_COLOR: RGB8 = RGB8 {
+    r: 0x80,
+    g: 0x80,
+    b: 0x80,
+};
+
+/// Color of the users merged past [`Settings::max_contributors`], a lighter gray than
+/// [`UNKNOWN_COLOR`].
+const MERGED_COLOR: RGB8 = RGB8 {
+    r: 0xb0,
+    g: 0xb0,
+    b: 0xb0,
+};
+
+/// Key of the users merged past [`Settings::max_contributors`]. Spotify ids can't contain a colon,
+/// so no real user gets this key.
+fn merged_key() -> UserKey {
+    Some(UserId::from_id(":others").expect("user ids accept any string"))
+}
+
+/// A color as CSS writes it, `#rrggbb`.
+pub fn hex(color: RGB8) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+const fn hex_color(hex: u32) -> RGB8 {
+    RGB8 {
+        r: (hex >> 16) as u8,
+        g: (hex >> 8) as u8,
+        b: hex as u8,
+    }
+}
+
+/// The Okabe-Ito palette without its black, which the text on the ribbon wouldn't be readable on.
+/// With more users than colors the colors repeat.
+const COLORBLIND_PALETTE: [RGB8; 7] = [
+    hex_color(0xe69f00),
+    hex_color(0x56b4e9),
+    hex_color(0x009e73),
+    hex_color(0xf0e442),
+    hex_color(0x0072b2),
+    hex_color(0xd55e00),
+    hex_color(0xcc79a7),
+];
+
+/// Colors closer than this, see [`color_distance`], are nudged apart.
+const MIN_COLOR_DISTANCE: f64 = 60.0;
+
+/// How far the hue of a color that is too close to another is turned, each try.
+const HUE_NUDGE_DEGREES: f64 = 37.0;
+
+/// How perceptually different two colors are, the "redmean" approximation, from 0 to about 765.
+fn color_distance(a: RGB8, b: RGB8) -> f64 {
+    let red_mean = (a.r as f64 + b.r as f64) / 2.0;
+    let dr = a.r as f64 - b.r as f64;
+    let dg = a.g as f64 - b.g as f64;
+    let db = a.b as f64 - b.b as f64;
+    ((2.0 + red_mean / 256.0) * dr * dr
+        + 4.0 * dg * dg
+        + (2.0 + (255.0 - red_mean) / 256.0) * db * db)
+        .sqrt()
+}
+
+/// Turns the hue of a color, keeping its saturation and value.
+fn rotate_hue(color: RGB8, degrees: f64) -> RGB8 {
+    let [r, g, b] = [color.r, color.g, color.b].map(|c| c as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    if delta == 0.0 {
+        return color;
+    }
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let hue = (hue + degrees).rem_euclid(360.0);
+
+    let x = delta * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (delta, x, 0.0),
+        1 => (x, delta, 0.0),
+        2 => (0.0, delta, x),
+        3 => (0.0, x, delta),
+        4 => (x, 0.0, delta),
+        _ => (delta, 0.0, x),
+    };
+    let m = max - delta;
+    let to_u8 = |c: f64| ((c + m) * 255.0).round() as u8;
+    RGB8::new(to_u8(r), to_u8(g), to_u8(b))
+}
+
+/// The color of every user, seeded by their id. Users are handled in the order of their ids, and
+/// the hue of a color too close to one before it is turned until it stands apart, so the same users
+/// always get the same colors. With [`Settings::colorblind_palette`] the users instead go through
+/// [`COLORBLIND_PALETTE`] in that order.
+fn user_colors<'a>(
+    ids: impl Iterator<Item = &'a UserId<'static>>,
+    settings: &Settings,
+) -> HashMap<UserId<'static>, RGB8> {
+    let mut ids = ids.collect::<Vec<_>>();
+    ids.sort_unstable_by(|a, b| Borrow::<str>::borrow(*a).cmp(Borrow::<str>::borrow(*b)));
+
+    if settings.colorblind_palette {
+        // Shuffling turns the palette, the colors themselves are fixed.
+        let offset = settings.color_seed as usize % COLORBLIND_PALETTE.len();
+        return ids
+            .into_iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let color = COLORBLIND_PALETTE[(i + offset) % COLORBLIND_PALETTE.len()];
+                (id.clone(), color)
+            })
+            .collect();
+    }
+
+    let mut colors = Vec::<(UserId<'static>, RGB8)>::with_capacity(ids.len());
+    for id in ids {
+        // Seed 0 keeps the colors users had before the palette could be shuffled.
+        let seed = match settings.color_seed {
+            0 => Borrow::<str>::borrow(id).to_owned(),
+            salt => format!("{}:{salt}", Borrow::<str>::borrow(id)),
+        };
+        let mut color: RGB8 = RandomColor::new().seed(seed.as_str()).to_rgb_array().into();
+        // Gives up after a full turn, with many users some colors are bound to be close.
+        for _ in 0..(360.0 / HUE_NUDGE_DEGREES) as usize {
+            let too_close = colors
+                .iter()
+                .any(|&(_, other)| color_distance(color, other) < MIN_COLOR_DISTANCE);
+            if !too_close {
+                break;
+            }
+            color = rotate_hue(color, HUE_NUDGE_DEGREES);
+        }
+        colors.push((id.clone(), color));
+    }
+    colors.into_iter().collect()
+}
+
+/// Washes out a color, used for users that no longer exist.
+fn muted(color: RGB8) -> RGB8 {
+    let mute = |c: u8| ((c as u16 + 2 * 0xa0) / 3) as u8;
+    RGB8::new(mute(color.r), mute(color.g), mute(color.b))
+}
+
+/// The average length of a month.
+const DAYS_PER_MONTH: f64 = 365.25 / 12.0;
+
+/// When sizing by recency, a track's width halves every this many days.
+const RECENCY_HALF_LIFE_DAYS: f64 = 90.0;
+
+/// Keeps the oldest tracks visible when sizing by recency.
+const MIN_RECENCY_WEIGHT: f64 = 0.05;
+
+/// Tracks without `added_at` are sized as if they were this old.
+const UNKNOWN_AGE_DAYS: i64 = 365;
+
+/// Tracks added more recently than this show as added just now instead of 0 days ago.
+const JUST_NOW: TimeDelta = TimeDelta::hours(1);
+
+/// Color of the least popular tracks when coloring by popularity.
+const DEEP_CUT_COLOR: RGB8 = RGB8 {
+    r: 0x1b,
+    g: 0x9e,
+    b: 0x77,
+};
+/// Color of the most popular tracks when coloring by popularity.
+const HIT_COLOR: RGB8 = RGB8 {
+    r: 0xf0,
+    g: 0x8a,
+    b: 0x24,
+};
+
+/// Color of the tracks that were just added when coloring by age.
+const FRESH_COLOR: RGB8 = hex_color(0x2c7fb8);
+/// Color of the tracks that are [`Settings::old_after_days`] or older when coloring by age.
+const STALE_COLOR: RGB8 = hex_color(0xd95f0e);
+
+/// The color of a track `age` of the way from just added to old, see [`TrackInfo::age`].
+pub fn age_color(age: f64) -> RGB8 {
+    mix(FRESH_COLOR, STALE_COLOR, age.clamp(0.0, 1.0))
+}
+
+fn popularity_color(popularity: Option<u32>) -> RGB8 {
+    let Some(popularity) = popularity else {
+        return UNKNOWN_COLOR;
+    };
+    let t = popularity.min(100) as f64 / 100.0;
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    RGB8::new(
+        mix(DEEP_CUT_COLOR.r, HIT_COLOR.r),
+        mix(DEEP_CUT_COLOR.g, HIT_COLOR.g),
+        mix(DEEP_CUT_COLOR.b, HIT_COLOR.b),
+    )
+}
+
+pub const WHITE: RGB8 = hex_color(0xffffff);
+const BLACK: RGB8 = hex_color(0x000000);
+
+/// Mixes `amount` of `other` into `color`, like CSS's `color-mix` in srgb.
+pub fn mix(color: RGB8, other: RGB8, amount: f64) -> RGB8 {
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * amount).round() as u8;
+    RGB8::new(
+        mix(color.r, other.r),
+        mix(color.g, other.g),
+        mix(color.b, other.b),
+    )
+}
+
+/// The color of a track cell as the ribbon shows it, see the `--age` there. Old tracks are mixed
+/// towards black, or white with [`Settings::fresh_emphasis`].
+pub fn aged(color: RGB8, age: f64, fresh_emphasis: bool) -> RGB8 {
+    mix(color, if fresh_emphasis { WHITE } else { BLACK }, age / 2.0)
+}
+
+/// The relative luminance of a color as WCAG defines it, from 0 for black to 1 for white.
+pub fn luminance(color: RGB8) -> f64 {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(color.r) + 0.7152 * linear(color.g) + 0.0722 * linear(color.b)
+}
+
+/// Above this luminance black text contrasts more than white text does.
+const TEXT_LUMINANCE_THRESHOLD: f64 = 0.179;
+
+/// Black or white, whichever is more readable on `background`, as a CSS color.
+pub fn text_color(background: RGB8) -> &'static str {
+    if luminance(background) > TEXT_LUMINANCE_THRESHOLD {
+        "black"
+    } else {
+        "white"
+    }
+}
+
+/// The playlist as if only `user` added anything, their tracks sized against each other.
+pub fn focus(playlist: &PlaylistInfo, user: &UserKey) -> PlaylistInfo {
+    let mut tracks = playlist
+        .tracks
+        .iter()
+        .filter(|track| track.user == *user)
+        .cloned()
+        .collect::<Vec<_>>();
+    let total = tracks.iter().map(|track| track.relative_size).sum::<f64>();
+    if total > 0.0 {
+        for track in &mut tracks {
+            track.relative_size /= total;
+        }
+    }
+    let users = playlist
+        .users
+        .iter()
+        .filter(|u| u.id == *user)
+        .map(|u| UserInfo {
+            relative_size: 1.0,
+            ..u.clone()
+        })
+        .collect();
+
+    PlaylistInfo {
+        tracks,
+        users,
+        ..playlist.clone()
+    }
+}
+
+/// How many tracks, starting at each track, are from the same album. Tracks that continue the album
+/// of the one before are `0`, tracks without an album are never grouped.
+pub fn album_runs(tracks: &[TrackInfo]) -> Vec<usize> {
+    let mut runs = vec![0; tracks.len()];
+    let mut start = 0;
+    for i in 1..=tracks.len() {
+        let same =
+            i < tracks.len() && tracks[i].album.is_some() && tracks[i].album == tracks[start].album;
+        if !same {
+            runs[start] = i - start;
+            start = i;
+        }
+    }
+    runs
+}
+
+/// Formats a fraction as a CSS percentage.
+pub fn percent(fraction: f64) -> String {
+    format!("{}%", fraction * 100.0)
+}
+
+/// Spotify shouldn't send negative durations, but if it does they count as empty.
+fn non_negative(dur: TimeDelta) -> TimeDelta {
+    dur.max(TimeDelta::zero())
+}
+
+/// How long ago `added_at` was. A track added in the future, because the local clock is behind
+/// Spotify's, was added just now.
+fn age(now: DateTime<Utc>, added_at: DateTime<Utc>) -> TimeDelta {
+    non_negative(now.signed_duration_since(added_at))
+}
+
+/// Whether `added_at` is too recent to be worth counting, see [`JUST_NOW`].
+fn is_just_now(now: DateTime<Utc>, added_at: DateTime<Utc>) -> bool {
+    age(now, added_at) < JUST_NOW
+}
+
+/// `part` as a fraction of `total`, `0` when the total is empty instead of `NaN`.
+pub fn fraction(part: TimeDelta, total: TimeDelta) -> f64 {
+    let total = total.num_milliseconds();
+    if total <= 0 {
+        return 0.0;
+    }
+    (part.num_milliseconds() as f64 / total as f64).clamp(0.0, 1.0)
+}
+
+/// Formats a duration like a clock, `M:SS`, or `H:MM:SS` once it's an hour or longer.
+pub fn display_duration(dur: &TimeDelta) -> String {
+    let dur = non_negative(*dur);
+    let hours = dur.num_hours();
+    let minutes = dur.num_minutes() % 60;
+    let seconds = dur.num_seconds() % 60;
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// Formats a total duration in the given style. Single tracks always use [`display_duration`].
+pub fn format_duration(dur: &TimeDelta, style: DurationStyle, locale: Locale) -> String {
+    match style {
+        DurationStyle::Clock => display_duration(dur),
+        DurationStyle::Human => {
+            let dur = non_negative(*dur);
+            let hours = dur.num_hours();
+            let minutes = dur.num_minutes() % 60;
+            let seconds = dur.num_seconds() % 60;
+            if hours > 0 {
+                locale.fill(Msg::HoursMinutes, &[&hours, &minutes])
+            } else if minutes > 0 {
+                locale.fill(Msg::MinutesSeconds, &[&minutes, &seconds])
+            } else {
+                locale.fill(Msg::Seconds, &[&seconds])
+            }
+        }
+    }
+}
+
+/// Marks every track that is in the playlist more than once, and counts the copies that came after
+/// the first one against whoever added them. Local files have no id, so they can only be possible
+/// duplicates.
+fn mark_duplicates(tracks: &mut [TrackInfo], users: &mut [UserInfo]) -> Duplicates {
+    let mut order = (0..tracks.len()).collect::<Vec<_>>();
+    order.sort_unstable_by_key(|&i| tracks[i].index);
+
+    let mut duplicates = Duplicates::default();
+    // Where the first copy of every track, and of every title with its artists, is in `tracks`.
+    let mut first_by_id = HashMap::<TrackId<'static>, usize>::new();
+    let mut first_by_song = HashMap::<(String, Vec<String>), usize>::new();
+    let mut later = Vec::new();
+    for i in order {
+        if let Some(id) = tracks[i].id.clone() {
+            match first_by_id.entry(id) {
+                Entry::Occupied(first) => {
+                    tracks[*first.get()].duplicate = Some(Duplicate::Exact);
+                    tracks[i].duplicate = Some(Duplicate::Exact);
+                    duplicates.exact += 1;
+                    later.push(i);
+                    continue;
+                }
+                Entry::Vacant(first) => {
+                    first.insert(i);
+                }
+            }
+        }
+
+        let song = (
+            tracks[i].name.to_lowercase(),
+            tracks[i]
+                .artists
+                .iter()
+                .map(|artist| artist.to_lowercase())
+                .collect(),
+        );
+        match first_by_song.entry(song) {
+            Entry::Occupied(first) => {
+                let first = &mut tracks[*first.get()].duplicate;
+                first.get_or_insert(Duplicate::Possible);
+                tracks[i].duplicate.get_or_insert(Duplicate::Possible);
+                duplicates.possible += 1;
+                later.push(i);
+            }
+            Entry::Vacant(first) => {
+                first.insert(i);
+            }
+        }
+    }
+
+    for i in later {
+        if let Some(user) = users.iter_mut().find(|user| user.id == tracks[i].user) {
+            user.duplicates += 1;
+        }
+    }
+    duplicates
+}
+
+pub fn build_playlist_info(
+    playlist: &FullPlaylist,
+    user_names: &HashMap<UserId<'static>, UserLookup>,
+    settings: &Settings,
+) -> PlaylistInfo {
+    let locale = settings.locale;
+    let name = playlist.name.clone();
+
+    let now = Utc::now();
+    let old_after_days = settings.old_after_days.max(1) as f64;
+    // What the width of a track in the ribbon is proportional to.
+    let weight = |duration: TimeDelta, added_at: Option<DateTime<Utc>>| match settings.ribbon_size {
+        RibbonSize::Duration => non_negative(duration).num_milliseconds() as f64,
+        RibbonSize::Recency => {
+            let days = added_at
+                .map(|added_at| age(now, added_at).num_days())
+                .unwrap_or(UNKNOWN_AGE_DAYS);
+            0.5f64
+                .powf(days as f64 / RECENCY_HALF_LIFE_DAYS)
+                .max(MIN_RECENCY_WEIGHT)
+        }
+    };
+
+    let mut total_duration = TimeDelta::default();
+    let mut total_weight = 0.0;
+    let mut user_id_to_track = HashMap::new();
+    let mut podcasts = None::<Podcasts>;
+
+    for (index, item) in playlist.tracks.items.iter().enumerate() {
+        let shown = match &item.track {
+            Some(PlayableItem::Track(track)) => Item::Track(track),
+            Some(PlayableItem::Episode(episode)) => match settings.episodes {
+                EpisodeStats::Include => Item::Episode(episode),
+                EpisodeStats::Separate => {
+                    let podcasts = podcasts.get_or_insert(Podcasts {
+                        episodes: 0,
+                        total_duration: TimeDelta::zero(),
+                    });
+                    podcasts.episodes += 1;
+                    podcasts.total_duration += non_negative(episode.duration);
+                    continue;
+                }
+            },
+            None => continue,
+        };
+        total_duration += non_negative(shown.duration());
+        total_weight += weight(shown.duration(), item.added_at);
+        user_id_to_track
+            .entry(item.added_by.as_ref().map(|u| u.id.clone()))
+            .or_insert_with(Vec::new)
+            .push((index, item.added_at, shown));
+    }
+
+    // Past a few dozen users the colors become indistinguishable and the headers unreadable, so
+    // only the biggest ones are kept apart.
+    let contributors = user_id_to_track.keys().filter(|id| id.is_some()).count();
+    let max_contributors = settings.max_contributors as usize;
+    let merged_users = if max_contributors > 0 && contributors > max_contributors {
+        let mut ranked = user_id_to_track
+            .iter()
+            .filter(|(id, _)| id.is_some())
+            .map(|(id, groups)| {
+                let weight = groups
+                    .iter()
+                    .map(|&(_, added_at, item)| weight(item.duration(), added_at))
+                    .sum::<f64>();
+                (id.clone(), weight)
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_unstable_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut merged = Vec::new();
+        for (id, _) in ranked.into_iter().skip(max_contributors) {
+            merged.append(&mut user_id_to_track.remove(&id).unwrap_or_default());
+        }
+        merged.sort_unstable_by_key(|&(index, _, _)| index);
+        user_id_to_track.insert(merged_key(), merged);
+        contributors - max_contributors
+    } else {
+        0
+    };
+
+    let merged_id = merged_key();
+    let colors = user_colors(
+        user_id_to_track
+            .keys()
+            .filter(|&id| *id != merged_id)
+            .flatten(),
+        settings,
+    );
+
+    let recent = TimeDelta::days(settings.recent_days.into());
+    let mut data = user_id_to_track
+        .into_iter()
+        .map(|(user_id, groups)| {
+            let lookup = user_id.as_ref().and_then(|id| user_names.get(id));
+            let merged = merged_users > 0 && user_id == merged_key();
+
+            let color = match &user_id {
+                Some(_) if merged => MERGED_COLOR,
+                Some(id) => {
+                    let color = colors.get(id).copied().unwrap_or(UNKNOWN_COLOR);
+                    match lookup {
+                        Some(UserLookup::Deleted) => muted(color),
+                        _ => color,
+                    }
+                }
+                None => UNKNOWN_COLOR,
+            };
+
+            let ages = AgeSpread::new(
+                groups
+                    .iter()
+                    .filter_map(|(_, added_at, _)| *added_at)
+                    .map(|added_at| age(now, added_at).num_days()),
+            );
+
+            let dated_tracks = groups
+                .iter()
+                .filter(|(_, added_at, _)| added_at.is_some())
+                .count();
+            // Everything added within a month counts as a month, or a burst of additions on one
+            // day would give an absurd rate.
+            let tracks_per_month = ages.filter(|_| dated_tracks > 1).map(|ages| {
+                let months = ((ages.max - ages.min) / DAYS_PER_MONTH).max(1.0);
+                dated_tracks as f64 / months
+            });
+
+            // Would be the favorite of nobody in particular.
+            let favorite = (!merged)
+                .then(|| {
+                    Favorite::new(
+                        &groups
+                            .iter()
+                            .filter_map(|&(_, _, item)| item.track())
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .flatten();
+
+            let mut user_tracks = groups
+                .into_iter()
+                .map(|(index, added_at, item)| {
+                    let age_days = added_at.map(|added_at| age(now, added_at).num_days());
+                    let is_recent = added_at.is_some_and(|added_at| age(now, added_at) < recent);
+                    let just_now = added_at.is_some_and(|added_at| is_just_now(now, added_at));
+                    let age = (age_days.unwrap_or(0) as f64 / old_after_days).clamp(0.0, 1.0);
+                    // An episode's show stands in for the album, its publisher for the artist.
+                    let (id, name, artists, album, popularity) = match item {
+                        Item::Track(track) => (
+                            track.id.clone(),
+                            track.name.clone(),
+                            track
+                                .artists
+                                .iter()
+                                .map(|artist| artist.name.clone())
+                                .collect(),
+                            track.album.name.clone(),
+                            (!track.is_local).then_some(track.popularity),
+                        ),
+                        Item::Episode(episode) => (
+                            None,
+                            episode.name.clone(),
+                            vec![episode.show.publisher.clone()],
+                            episode.show.name.clone(),
+                            None,
+                        ),
+                    };
+
+                    TrackInfo {
+                        index,
+                        id,
+                        user: user_id.clone(),
+                        name,
+                        artists,
+                        album: Some(album).filter(|name| !name.is_empty()),
+                        is_episode: matches!(item, Item::Episode(_)),
+                        url: item.url(),
+                        duration: non_negative(item.duration()),
+                        relative_size: if total_weight > 0.0 {
+                            weight(item.duration(), added_at) / total_weight
+                        } else {
+                            0.0
+                        },
+                        color: match settings.track_coloring {
+                            TrackColoring::User => color,
+                            TrackColoring::Popularity => popularity_color(popularity),
+                            TrackColoring::Age if age_days.is_none() => UNKNOWN_COLOR,
+                            TrackColoring::Age => age_color(age),
+                        },
+                        age,
+                        extreme: None,
+                        is_recent,
+                        popularity,
+                        added_at,
+                        age_days,
+                        just_now,
+                        duplicate: None,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            user_tracks.sort_unstable_by_key(|a| a.duration);
+
+            let user_name = match (lookup, &user_id) {
+                _ if merged => locale.fill(Msg::MergedUsers, &[&merged_users]),
+                (Some(UserLookup::Found { name, .. }), _) => name.clone(),
+                (Some(UserLookup::Deleted), Some(id)) => {
+                    locale.fill(Msg::FormerUser, &[&Borrow::<str>::borrow(id)])
+                }
+                (Some(UserLookup::Failed), _) => locale.text(Msg::FailedUser).to_owned(),
+                _ => locale.text(Msg::UnknownUser).to_owned(),
+            };
+
+            let user_total_duration: TimeDelta = user_tracks.iter().map(|t| &t.duration).sum();
+
+            let popularities = user_tracks
+                .iter()
+                .filter_map(|t| t.popularity)
+                .collect::<Vec<_>>();
+            let average_popularity = (!popularities.is_empty())
+                .then(|| popularities.iter().sum::<u32>() as f64 / popularities.len() as f64);
+
+            let user = UserInfo {
+                id: user_id,
+                name: user_name,
+                relative_size: user_tracks.iter().map(|t| t.relative_size).sum(),
+                total_duration: user_total_duration,
+                amount_of_tracks: user_tracks.len() as u64,
+                color,
+                ages,
+                favorite,
+                average_popularity,
+                tracks_per_month,
+                merged,
+                duplicates: 0,
+                avatar: match lookup {
+                    Some(UserLookup::Found { avatar, .. }) if !merged => avatar.clone(),
+                    _ => None,
+                },
+                lookup_failed: !merged && lookup == Some(&UserLookup::Failed),
+            };
+            (user, user_tracks)
+        })
+        .collect::<Vec<_>>();
+
+    data.sort_unstable_by(|a, b| a.0.relative_size.total_cmp(&b.0.relative_size));
+    if settings.biggest_first {
+        // Mirrors the whole ribbon, so every user's tracks still grow towards the next user.
+        data.reverse();
+        for (_, user_tracks) in &mut data {
+            user_tracks.reverse();
+        }
+    }
+
+    let mut tracks = Vec::new();
+    let mut users = Vec::new();
+    for (user, mut user_tracks) in data {
+        tracks.append(&mut user_tracks);
+        users.push(user);
+    }
+    if settings.ribbon_order == RibbonOrder::Added {
+        tracks.sort_by_key(|track| (track.added_at.is_none(), track.added_at, track.index));
+    }
+
+    // Ties go to the track that comes first in the playlist. With a single track it is only marked
+    // as the longest.
+    let longest = tracks
+        .iter()
+        .max_by(|a, b| a.duration.cmp(&b.duration).then(b.index.cmp(&a.index)))
+        .map(|track| track.index);
+    let shortest = tracks
+        .iter()
+        .min_by(|a, b| a.duration.cmp(&b.duration).then(a.index.cmp(&b.index)))
+        .map(|track| track.index)
+        .filter(|&index| Some(index) != longest);
+    for track in &mut tracks {
+        if Some(track.index) == longest {
+            track.extreme = Some(Extreme::Longest);
+        } else if Some(track.index) == shortest {
+            track.extreme = Some(Extreme::Shortest);
+        }
+    }
+
+    let duplicates = mark_duplicates(&mut tracks, &mut users);
+    let most_added = MostAdded::new(&tracks, &users);
+    let mut additions = HashMap::<NaiveDate, usize>::new();
+    for added_at in tracks.iter().filter_map(|track| track.added_at) {
+        *additions.entry(added_at.date_naive()).or_default() += 1;
+    }
+    let active_days = additions.len();
+    let busiest_day = additions
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map(|(date, count)| BusiestDay { date, count });
+
+    PlaylistInfo {
+        name,
+        total_duration,
+        tracks,
+        users,
+        most_added,
+        active_days,
+        busiest_day,
+        podcasts,
+        duplicates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn user(id: &str) -> Value {
+        json!({
+            "display_name": id,
+            "external_urls": {},
+            "href": format!("https://api.spotify.com/v1/users/{id}"),
+            "id": id,
+            "images": [],
+            "type": "user",
+            "uri": format!("spotify:user:{id}"),
+        })
+    }
+
+    /// A playlist item of a track, `n` makes up its id and name.
+    fn item(n: u32, duration_ms: u32, added_by: Option<&str>) -> Value {
+        json!({
+            "added_at": "2024-01-01T00:00:00Z",
+            "added_by": added_by.map(user),
+            "is_local": false,
+            "track": {
+                "album": {
+                    "album_type": "album",
+                    "artists": [],
+                    "available_markets": [],
+                    "external_urls": {},
+                    "images": [],
+                    "name": "Album",
+                },
+                "artists": [{ "external_urls": {}, "name": "Artist" }],
+                "available_markets": [],
+                "disc_number": 1,
+                "duration_ms": duration_ms,
+                "explicit": false,
+                "external_ids": {},
+                "external_urls": {},
+                "id": format!("{n:0>22}"),
+                "is_local": false,
+                "name": format!("Track {n}"),
+                "popularity": 50,
+                "track_number": 1,
+                "type": "track",
+            },
+        })
+    }
+
+    fn playlist(items: Vec<Value>) -> FullPlaylist {
+        let total = items.len();
+        serde_json::from_value(json!({
+            "collaborative": true,
+            "external_urls": {},
+            "followers": { "total": 0 },
+            "href": "https://api.spotify.com/v1/playlists/37i9dQZF1DXcBWIGoYBM5M",
+            "id": "37i9dQZF1DXcBWIGoYBM5M",
+            "images": [],
+            "name": "Playlist",
+            "owner": user("owner"),
+            "snapshot_id": "snapshot",
+            "tracks": {
+                "href": "https://api.spotify.com/v1/playlists/37i9dQZF1DXcBWIGoYBM5M/tracks",
+                "items": items,
+                "limit": 100,
+                "offset": 0,
+                "total": total,
+            },
+            "type": "playlist",
+        }))
+        .expect("a valid playlist")
+    }
+
+    #[test]
+    fn future_tracks_were_added_just_now() {
+        let now = Utc::now();
+        assert_eq!(age(now, now + TimeDelta::days(3)), TimeDelta::zero());
+        assert_eq!(age(now, now - TimeDelta::days(3)), TimeDelta::days(3));
+        assert!(is_just_now(now, now + TimeDelta::days(3)));
+        assert!(is_just_now(now, now - TimeDelta::minutes(5)));
+        assert!(!is_just_now(now, now - JUST_NOW));
+        assert!(!is_just_now(now, now - TimeDelta::days(3)));
+    }
+
+    #[test]
+    fn colliding_users_get_distinct_colors() {
+        let seeded = |id: &UserId| -> RGB8 {
+            let seed = Borrow::<str>::borrow(id);
+            RandomColor::new().seed(seed).to_rgb_array().into()
+        };
+        let ids = (0..200)
+            .map(|n| UserId::from_id(format!("user{n}")).unwrap())
+            .collect::<Vec<_>>();
+        // Two users whose seeds alone would make them look alike.
+        let (a, b) = ids
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| ids[i + 1..].iter().map(move |b| (a, b)))
+            .find(|(a, b)| color_distance(seeded(a), seeded(b)) < MIN_COLOR_DISTANCE)
+            .expect("some seeds to collide");
+
+        let colors = user_colors([a, b].into_iter(), &Settings::default());
+        assert!(color_distance(colors[a], colors[b]) >= MIN_COLOR_DISTANCE);
+    }
+
+    #[test]
+    fn text_color_at_the_extremes() {
+        assert_eq!(luminance(BLACK), 0.0);
+        assert!((luminance(WHITE) - 1.0).abs() < 1e-9);
+        assert_eq!(text_color(BLACK), "white");
+        assert_eq!(text_color(WHITE), "black");
+    }
+
+    #[test]
+    fn text_color_flips_at_mid_gray() {
+        let gray = |c: u8| RGB8::new(c, c, c);
+        assert!(luminance(gray(117)) < TEXT_LUMINANCE_THRESHOLD);
+        assert!(luminance(gray(118)) > TEXT_LUMINANCE_THRESHOLD);
+        assert_eq!(text_color(gray(117)), "white");
+        assert_eq!(text_color(gray(118)), "black");
+    }
+
+    #[test]
+    fn non_negative_clamps_below_zero() {
+        assert_eq!(non_negative(TimeDelta::seconds(-5)), TimeDelta::zero());
+        assert_eq!(non_negative(TimeDelta::seconds(5)), TimeDelta::seconds(5));
+    }
+
+    #[test]
+    fn fraction_of_an_empty_total_is_zero() {
+        assert_eq!(fraction(TimeDelta::seconds(5), TimeDelta::zero()), 0.0);
+        assert_eq!(
+            fraction(TimeDelta::seconds(5), TimeDelta::seconds(-10)),
+            0.0
+        );
+        assert_eq!(
+            fraction(TimeDelta::seconds(-5), TimeDelta::seconds(10)),
+            0.0
+        );
+        assert_eq!(
+            fraction(TimeDelta::seconds(5), TimeDelta::seconds(20)),
+            0.25
+        );
+    }
+
+    #[test]
+    fn empty_playlist_has_no_users() {
+        let info =
+            build_playlist_info(&playlist(Vec::new()), &HashMap::new(), &Settings::default());
+        assert!(info.tracks.is_empty());
+        assert!(info.users.is_empty());
+        assert_eq!(info.total_duration, TimeDelta::zero());
+    }
+
+    #[test]
+    fn relative_sizes_add_up() {
+        let items = vec![
+            item(1, 60_000, Some("a")),
+            item(2, 180_000, Some("b")),
+            item(3, 0, Some("b")),
+        ];
+        let info = build_playlist_info(&playlist(items), &HashMap::new(), &Settings::default());
+        assert_eq!(info.total_duration, TimeDelta::minutes(4));
+        let tracks = info.tracks.iter().map(|t| t.relative_size).sum::<f64>();
+        let users = info.users.iter().map(|u| u.relative_size).sum::<f64>();
+        assert!((tracks - 1.0).abs() < 1e-9);
+        assert!((users - 1.0).abs() < 1e-9);
+        assert!(info.tracks.iter().all(|t| t.relative_size.is_finite()));
+    }
+
+    #[test]
+    fn tracks_without_added_by_are_unknown() {
+        let items = vec![
+            item(1, 60_000, Some("alice")),
+            item(2, 120_000, None),
+            item(3, 30_000, Some("bob")),
+            item(4, 90_000, None),
+            item(5, 60_000, Some("alice")),
+        ];
+        let alice = UserId::from_id("alice").unwrap();
+        let lookups = HashMap::from([(
+            alice.clone(),
+            UserLookup::Found {
+                name: "Alice".to_owned(),
+                avatar: None,
+            },
+        )]);
+        let settings = Settings::default();
+        let info = build_playlist_info(&playlist(items), &lookups, &settings);
+        assert_eq!(info.users.len(), 3);
+        assert_eq!(info.total_duration, TimeDelta::minutes(6));
+
+        let unknown = info.users.iter().find(|u| u.id.is_none()).unwrap();
+        assert_eq!(unknown.name, settings.locale.text(Msg::UnknownUser));
+        assert_eq!(unknown.color, UNKNOWN_COLOR);
+        assert_eq!(unknown.amount_of_tracks, 2);
+        assert_eq!(unknown.total_duration, TimeDelta::seconds(210));
+        assert!((unknown.relative_size - 210.0 / 360.0).abs() < 1e-9);
+
+        let known = info
+            .users
+            .iter()
+            .find(|u| u.id == Some(alice.clone()))
+            .unwrap();
+        assert_eq!(known.name, "Alice");
+        assert_eq!(known.amount_of_tracks, 2);
+        assert_eq!(known.total_duration, TimeDelta::minutes(2));
+
+        let unknown_tracks = info.tracks.iter().filter(|t| t.user.is_none()).count();
+        assert_eq!(unknown_tracks, 2);
+    }
+
+    #[test]
+    fn display_duration_pads_seconds() {
+        assert_eq!(display_duration(&TimeDelta::zero()), "0:00");
+        assert_eq!(display_duration(&TimeDelta::seconds(5 * 60 + 3)), "5:03");
+        assert_eq!(display_duration(&TimeDelta::seconds(5 * 60 + 30)), "5:30");
+    }
+
+    #[test]
+    fn format_duration_clock_ignores_locale() {
+        let dur = TimeDelta::seconds(3600 + 5 * 60 + 9);
+        for locale in Locale::ALL {
+            assert_eq!(
+                format_duration(&dur, DurationStyle::Clock, locale),
+                "1:05:09"
+            );
+        }
+    }
+
+    #[test]
+    fn format_duration_human() {
+        let human = |seconds, locale| {
+            format_duration(&TimeDelta::seconds(seconds), DurationStyle::Human, locale)
+        };
+        assert_eq!(human(3600 + 5 * 60 + 9, Locale::English), "1h 5m");
+        assert_eq!(human(5 * 60 + 9, Locale::English), "5m 9s");
+        assert_eq!(human(9, Locale::English), "9s");
+        assert_eq!(human(3600 + 5 * 60 + 9, Locale::Dutch), "1u 5m");
+        assert_eq!(human(5 * 60 + 9, Locale::Dutch), "5m 9s");
+        assert_eq!(human(9, Locale::Dutch), "9s");
+    }
+
+    #[test]
+    fn display_duration_adds_hours() {
+        assert_eq!(display_duration(&TimeDelta::seconds(42)), "0:42");
+        assert_eq!(display_duration(&TimeDelta::seconds(59 * 60 + 59)), "59:59");
+        assert_eq!(display_duration(&TimeDelta::hours(1)), "1:00:00");
+        assert_eq!(
+            display_duration(&TimeDelta::seconds(3600 + 5 * 60 + 9)),
+            "1:05:09"
+        );
+        assert_eq!(
+            display_duration(&TimeDelta::seconds(12 * 3600 + 34 * 60 + 56)),
+            "12:34:56"
+        );
+    }
+}
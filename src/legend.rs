@@ -0,0 +1,142 @@
+use leptos::{
+    component, expect_context, view, IntoView, RwSignal, Show, Signal, SignalSet, SignalWith,
+};
+
+use crate::{
+    i18n::{use_i18n, Msg},
+    info::{age_color, percent, Favorite, PlaylistInfo, UserKey},
+    settings::{Settings, TrackColoring},
+};
+
+/// Every user with their color. Hovering an entry sets `hovered`, which dims the tracks of the other
+/// users in the ribbon.
+///
+/// When the tracks are colored by age the users have no color of their own, so the legend shows
+/// the age scale instead.
+#[component]
+pub fn Legend(
+    #[prop(into)] playlist: Signal<PlaylistInfo>,
+    hovered: RwSignal<Option<UserKey>>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+    let by_age = move || settings.with(|s| s.track_coloring == TrackColoring::Age);
+    let gradient = format!(
+        "linear-gradient(to right, {}, {})",
+        age_color(0.0),
+        age_color(1.0),
+    );
+
+    view! {
+        <ul class="legend">
+            <Show when=by_age>
+                <li class="legend-entry">
+                    <span>{move || i18n.t(Msg::AgeScaleFresh)}</span>
+                    <span class="legend-age-scale" style:background=gradient.clone()></span>
+                    <span>
+                        {move || {
+                            i18n.fill(Msg::AgeScaleStale, &[&settings.with(|s| s.old_after_days)])
+                        }}
+                    </span>
+                </li>
+            </Show>
+            {move || {
+                if by_age() {
+                    return Vec::new();
+                }
+                playlist
+                    .with(|playlist| {
+                        // The age bars of all users share one scale, so they can be compared.
+                        let oldest = playlist
+                            .users
+                            .iter()
+                            .filter_map(|user| user.ages)
+                            .map(|ages| ages.max)
+                            .fold(0.0, f64::max)
+                            .max(1.0);
+
+                        playlist
+                            .users
+                            .iter()
+                            .map(|user| {
+                                let ages = user
+                                    .ages
+                                    .map(|ages| {
+                                        let locale = i18n.locale();
+                                        let title = locale
+                                            .fill(
+                                                Msg::AgeSpread,
+                                                &[
+                                                    &locale.number(ages.min),
+                                                    &locale.number(ages.max),
+                                                    &locale.number(ages.median),
+                                                ],
+                                            );
+                                        view! {
+                                            <span class="legend-ages" title=title>
+                                                <span
+                                                    class="legend-ages-range"
+                                                    style:left=percent(ages.min / oldest)
+                                                    style:width=percent((ages.max - ages.min) / oldest)
+                                                ></span>
+                                                <span
+                                                    class="legend-ages-median"
+                                                    style:left=percent(ages.median / oldest)
+                                                ></span>
+                                            </span>
+                                        }
+                                    });
+                                let favorite = user
+                                    .favorite
+                                    .as_ref()
+                                    .map(|favorite| {
+                                        let (msg, name) = match favorite {
+                                            Favorite::Artist(name) => (Msg::FavoriteArtist, name),
+                                            Favorite::Album(name) => (Msg::FavoriteAlbum, name),
+                                        };
+                                        view! {
+                                            <span class="legend-favorite">{i18n.fill(msg, &[name])}</span>
+                                        }
+                                    });
+                                let rate = user
+                                    .tracks_per_month
+                                    .map(|rate| {
+                                        let rate = i18n.locale().number((rate * 10.0).round() / 10.0);
+                                        view! {
+                                            <span class="legend-rate">
+                                                {i18n.fill(Msg::TracksPerMonth, &[&rate])}
+                                            </span>
+                                        }
+                                    });
+                                let share = i18n
+                                    .fill(
+                                        Msg::LegendShare,
+                                        &[
+                                            &user.amount_of_tracks,
+                                            &i18n.locale().percentage(user.relative_size),
+                                        ],
+                                    );
+                                let id = user.id.clone();
+                                view! {
+                                    <li
+                                        class="legend-entry"
+                                        style=("--color", user.color.to_string())
+                                        on:mouseenter=move |_| hovered.set(Some(id.clone()))
+                                        on:mouseleave=move |_| hovered.set(None)
+                                    >
+                                        <span class="legend-swatch"></span>
+                                        <span class="legend-name">{user.name.clone()}</span>
+                                        <span class="legend-share">{share}</span>
+                                        {ages}
+                                        {rate}
+                                        {favorite}
+                                    </li>
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                    })
+            }}
+
+        </ul>
+    }
+}
@@ -1,41 +1,168 @@
+mod api;
 mod app;
+mod diagnostics;
+mod error;
+mod export;
+mod i18n;
+mod info;
+mod legend;
+mod race;
+mod ribbon;
+mod settings;
+mod share;
+mod snapshot;
+mod summary;
+mod table;
+mod timeline;
+
+use std::collections::HashSet;
 
 use leptos::{
-    component, create_effect, create_owning_memo, create_resource, expect_context, mount_to_body,
-    provide_context, view, window, IntoView, Memo, Signal, SignalGet, SignalGetUntracked,
-    SignalWithUntracked, WriteSignal,
+    component, create_effect, create_local_resource, create_owning_memo, create_resource,
+    expect_context, mount_to_body, provide_context, view, window, Callback, IntoView, Memo, Show,
+    Signal, SignalGet, SignalGetUntracked, SignalSet, SignalWith, SignalWithUntracked, WriteSignal,
 };
 use leptos_router::{
-    use_navigate, use_query_map, NavigateOptions, Outlet, ParamsMap, Route, Router, Routes,
+    use_navigate, use_query_map, NavigateOptions, Outlet, ParamsMap, Route, Router, Routes, A,
 };
-use leptos_use::{storage::use_local_storage, utils::JsonCodec};
+use leptos_use::{storage::use_local_storage, use_media_query, utils::JsonCodec};
 use rspotify::{
     clients::{BaseClient, OAuthClient},
     scopes, AuthCodePkceSpotify, Credentials, OAuth, Token,
 };
 
-use crate::app::{MainPage, Playlist};
+use crate::{
+    api::{
+        display_name, status_code, with_network_backoff, CurrentUser, CurrentUserResource, Limiter,
+        Priority, Session, MAX_CONCURRENT_REQUESTS, MAX_REQUESTS_PER_SECOND,
+    },
+    app::{use_embed, use_playlist_cache, MainPage, NotFound, Playlist},
+    diagnostics::{use_diagnostics, Diagnostics, DiagnosticsPanel},
+    export::Import,
+    i18n::{use_i18n, Msg},
+    settings::{ReducedMotion, Settings},
+};
+
+const SPOTIFY_API_ID: &str = "e88dbb278f734122875172d70978e455";
+
+/// Where the site is hosted, the name of the repository on GitHub Pages. Deep links get here
+/// through `404.html`.
+pub const BASE_PATH: &str = "/collab-playlist";
 
-const SPOTIFY_API_ID: &'static str = "e88dbb278f734122875172d70978e455";
+/// A path within the site.
+pub fn app_path(path: &str) -> String {
+    format!("{BASE_PATH}{path}")
+}
+
+/// The scopes the app can't work without. Tokens granted before one was added here are missing it,
+/// those users have to connect again.
+fn required_scopes() -> HashSet<String> {
+    scopes!("playlist-read-collaborative")
+}
+
+/// The redirect uris registered for [`SPOTIFY_API_ID`] on Spotify's dashboard, Spotify refuses to
+/// send the user back anywhere else. The hosted site, and `trunk serve` for development.
+const REDIRECT_URIS: [&str; 3] = [
+    "https://ebbdrop.github.io/collab-playlist/callback",
+    "http://127.0.0.1:8080/collab-playlist/callback",
+    "http://[::1]:8080/collab-playlist/callback",
+];
+
+/// Where Spotify sends the user back to, on the origin the site is served from. Spotify only
+/// redirects to https, or http on a loopback address, and only to [`REDIRECT_URIS`], the error
+/// explains why this origin won't do.
+fn redirect_uri() -> Result<String, Msg> {
+    let origin = window()
+        .location()
+        .origin()
+        .ok()
+        .filter(|origin| origin != "null")
+        .ok_or(Msg::RedirectNoOrigin)?;
+    let loopback = ["http://127.0.0.1", "http://[::1]"]
+        .into_iter()
+        .any(|loopback| origin == loopback || origin.starts_with(&format!("{loopback}:")));
+    if !origin.starts_with("https://") && !loopback {
+        return Err(Msg::RedirectInsecure);
+    }
+    let uri = format!("{origin}{}", app_path("/callback"));
+    if !REDIRECT_URIS.contains(&uri.as_str()) {
+        return Err(Msg::RedirectUnregistered);
+    }
+    Ok(uri)
+}
 
 fn init_spotify() -> AuthCodePkceSpotify {
-    let origin = window().location().origin().unwrap();
-    let redirect_uri = format!("{origin}/collab-playlist/callback");
     AuthCodePkceSpotify::new(
         Credentials::new_pkce(SPOTIFY_API_ID),
         OAuth {
-            redirect_uri,
-            scopes: scopes!("playlist-read-collaborative"),
+            // Login refuses to start without a valid one.
+            redirect_uri: redirect_uri().unwrap_or_default(),
+            scopes: required_scopes(),
             ..Default::default()
         },
     )
 }
 
-async fn get_token(query_map: Memo<ParamsMap>, spotify: AuthCodePkceSpotify) -> Option<Token> {
-    let code = query_map.with_untracked(|querys| querys.get("code").cloned())?;
-    spotify.request_token(code.as_str()).await.ok()?;
+/// Why the code Spotify redirected back with couldn't be exchanged for a token.
+struct TokenError {
+    /// Explains to the user why they have to log in again, see [`login_reason`].
+    reason: Option<&'static str>,
+    /// What went wrong, for the diagnostics.
+    message: String,
+}
 
-    spotify.get_token().lock().await.ok()?.clone()
+impl From<&str> for TokenError {
+    fn from(message: &str) -> Self {
+        Self {
+            reason: None,
+            message: message.to_owned(),
+        }
+    }
+}
+
+/// Exchanges the code Spotify redirected back with for a token. Only network errors are retried, if
+/// the code never reached Spotify it can still be used. Any answer may have spent it.
+///
+/// The callback has to bring back the `state` of the login that was started here, otherwise
+/// someone else could have sent the user here with a code for their own account.
+async fn get_token(
+    query_map: Memo<ParamsMap>,
+    spotify: AuthCodePkceSpotify,
+) -> Result<Token, TokenError> {
+    let state = query_map.with_untracked(|querys| querys.get("state").cloned());
+    if spotify.oauth.state.is_empty() || state.as_ref() != Some(&spotify.oauth.state) {
+        return Err(TokenError {
+            reason: Some("state-mismatch"),
+            message: "the state does not match the login".to_owned(),
+        });
+    }
+
+    let code = query_map
+        .with_untracked(|querys| querys.get("code").cloned())
+        .ok_or("the callback has no code")?;
+    let (spotify_ref, code) = (&spotify, code.as_str());
+    with_network_backoff(|| spotify_ref.request_token(code))
+        .await
+        .map_err(|err| TokenError {
+            // Spotify answers `invalid_grant` with a bad request.
+            reason: (status_code(&err) == Some(400)).then_some("code-spent"),
+            message: format!("requesting the token failed: {err}"),
+        })?;
+
+    spotify
+        .get_token()
+        .lock()
+        .await
+        .map_err(|_| "the token was locked")?
+        .clone()
+        .ok_or_else(|| "Spotify returned no token".into())
+}
+
+/// Spotify redirects back with an `error`, and maybe a description, instead of a `code` when it
+/// won't log the user in, like when they declined.
+fn callback_error(querys: &ParamsMap) -> Option<(String, Option<String>)> {
+    let error = querys.get("error")?.clone();
+    Some((error, querys.get("error_description").cloned()))
 }
 
 #[component]
@@ -44,39 +171,90 @@ fn Callback(
     set_oauth_flow: WriteSignal<OAuthFlow>,
 ) -> impl IntoView {
     let spotify = expect_context::<Memo<AuthCodePkceSpotify>>();
+    let diagnostics = use_diagnostics();
+    let i18n = use_i18n();
+
+    let query_map = use_query_map();
+    let error = move || query_map.with(callback_error);
 
     create_resource(
         move || use_query_map(),
         move |query_map| async move {
             let navigate = use_navigate();
+            if let Some((error, description)) = query_map.with_untracked(callback_error) {
+                let description = description.map(|d| format!(": {d}")).unwrap_or_default();
+                diagnostics.record(
+                    "callback: Spotify refused",
+                    Some(format!("{error}{description}")),
+                );
+                return;
+            }
             match oauth_flow_state.get_untracked() {
                 OAuthFlowState::RequestedUserAuthorization => {
                     let spotify = spotify.get_untracked();
 
                     match get_token(query_map, spotify).await {
-                        Some(token) => {
+                        Ok(token) => {
+                            diagnostics.record("callback: got token", None);
                             set_oauth_flow(OAuthFlow::GotToken { token });
-                            navigate("/collab-playlist", NavigateOptions::default())
+                            navigate(BASE_PATH, NavigateOptions::default())
+                        }
+                        Err(err) => {
+                            diagnostics.record("callback: no token", Some(err.message));
+                            let path = match err.reason {
+                                Some(reason) => app_path(&format!("/login?reason={reason}")),
+                                None => app_path("/login"),
+                            };
+                            navigate(&path, NavigateOptions::default())
                         }
-                        None => navigate("/collab-playlist/login", NavigateOptions::default()),
                     }
                 }
-                _ => navigate("/collab-playlist/login", NavigateOptions::default()),
+                state => {
+                    diagnostics.record(format!("callback: unexpected in state {state:?}"), None);
+                    navigate(&app_path("/login"), NavigateOptions::default())
+                }
             }
         },
     );
+
+    view! {
+        {move || {
+            error()
+                .map(|(error, description)| {
+                    let message = if error == "access_denied" {
+                        i18n.t(Msg::AccessDenied).to_owned()
+                    } else {
+                        i18n.fill(Msg::SpotifyRefused, &[&description.unwrap_or(error)])
+                    };
+                    view! {
+                        <div class="callback-error">
+                            <p>{message}</p>
+                            <A href=app_path("/login")>{move || i18n.t(Msg::TryAgain)}</A>
+                        </div>
+                    }
+                })
+        }}
+    }
 }
 
 #[component(transparent)]
 fn Main(#[prop(into)] oauth_flow_state: Signal<OAuthFlowState>) -> impl IntoView {
+    let diagnostics = use_diagnostics();
+
     create_effect(move |_| {
         let navigate = use_navigate();
-        match oauth_flow_state.get() {
-            OAuthFlowState::FirstVisit => {
-                navigate("/collab-playlist/login", NavigateOptions::default())
-            }
+        let state = oauth_flow_state.get();
+        if state == OAuthFlowState::RequestedUserAuthorization {
+            // The user left for Spotify but never came back through the callback.
+            diagnostics.record("main: authorization was never finished", None);
+        }
+        match state {
+            OAuthFlowState::FirstVisit => navigate(&app_path("/login"), NavigateOptions::default()),
             OAuthFlowState::RequestedUserAuthorization => {
-                navigate("/collab-playlist/login", NavigateOptions::default())
+                navigate(&app_path("/login"), NavigateOptions::default())
+            }
+            OAuthFlowState::MissingScopes => {
+                navigate(&app_path("/login"), NavigateOptions::default())
             }
             OAuthFlowState::GotToken => {}
         }
@@ -91,19 +269,139 @@ fn Main(#[prop(into)] oauth_flow_state: Signal<OAuthFlowState>) -> impl IntoView
 }
 
 #[component]
-fn Login(set_oauth_flow: WriteSignal<OAuthFlow>) -> impl IntoView {
+fn Nav(
+    #[prop(into)] oauth_flow_state: Signal<OAuthFlowState>,
+    set_oauth_flow: WriteSignal<OAuthFlow>,
+) -> impl IntoView {
+    let embed = use_embed();
+    let i18n = use_i18n();
+    let diagnostics = use_diagnostics();
+    let current_user = expect_context::<CurrentUserResource>();
+    let queued = expect_context::<Limiter>().queued();
+    let waiting = move || queued.get() > 0;
+    let rate_limited = expect_context::<Session>().rate_limited();
+
+    // Through the router, a full page load would start the whole app over.
+    let expired = expect_context::<Session>().expired();
+    create_effect(move |_| {
+        if expired.get() {
+            expired.set(false);
+            use_navigate()(
+                &app_path("/login?reason=expired"),
+                NavigateOptions::default(),
+            );
+        }
+    });
+
+    let (_, _, forget_playlists) = use_playlist_cache();
+
+    // Spotify has no endpoint to revoke a token, forgetting it is all that can be done. The next
+    // login starts over with a new verifier. The playlists of the last visit go along with it.
+    // A `Callback`, unlike the closure removing the playlists it can be copied into the `Show`s.
+    let logout = Callback::new(move |()| {
+        diagnostics.record("nav: logged out", None);
+        forget_playlists();
+        set_oauth_flow(OAuthFlow::FirstVisit);
+        use_navigate()(&app_path("/login"), NavigateOptions::default());
+    });
+
+    view! {
+        <Show when=move || !embed()>
+            <nav>
+                <Show when=waiting>
+                    <span class="nav-queue">{move || i18n.fill(Msg::QueuedRequests, &[&queued.get()])}</span>
+                </Show>
+                <Show when=rate_limited>
+                    <span class="nav-rate-limited" role="status">{move || i18n.t(Msg::RateLimited)}</span>
+                </Show>
+                {move || {
+                    current_user
+                        .get()
+                        .flatten()
+                        .map(|user| {
+                            view! {
+                                <span class="nav-user">{i18n.fill(Msg::ConnectedAs, &[&user.name])}</span>
+                            }
+                        })
+                }}
+                <Show when=move || oauth_flow_state.get() == OAuthFlowState::GotToken>
+                    <button class="nav-logout" on:click=move |_| logout(())>
+                        {move || i18n.t(Msg::Logout)}
+                    </button>
+                </Show>
+
+            </nav>
+        </Show>
+    }
+}
+
+/// A thin bar along the top of the page while requests to Spotify are running.
+#[component]
+fn ProgressBar() -> impl IntoView {
+    let busy = expect_context::<Session>().busy();
+
+    view! {
+        <Show when=busy>
+            <div class="progress-bar" role="progressbar"></div>
+        </Show>
+    }
+}
+
+/// What to tell the user that got sent back to the login with `?reason=`.
+fn login_reason(reason: &str) -> Option<Msg> {
+    match reason {
+        "code-spent" => Some(Msg::CodeSpent),
+        "state-mismatch" => Some(Msg::StateMismatch),
+        "expired" => Some(Msg::SessionExpired),
+        _ => None,
+    }
+}
+
+#[component]
+fn Login(
+    #[prop(into)] oauth_flow_state: Signal<OAuthFlowState>,
+    set_oauth_flow: WriteSignal<OAuthFlow>,
+) -> impl IntoView {
+    let diagnostics = use_diagnostics();
+
+    let redirect_error = redirect_uri().err();
+
     let click = move |_| {
+        if let Some(err) = redirect_error {
+            diagnostics.record("login: no valid redirect uri", Some(format!("{err:?}")));
+            return;
+        }
         let mut spotify = init_spotify();
 
         let url = spotify.get_authorize_url(None).unwrap();
         let verifier = spotify.verifier.unwrap();
+        // Random for every `OAuth`, the callback has to bring it back.
+        let state = spotify.oauth.state;
 
-        set_oauth_flow(OAuthFlow::RequestedUserAuthorization { verifier });
+        diagnostics.record("login: redirecting to Spotify", None);
+        set_oauth_flow(OAuthFlow::RequestedUserAuthorization { verifier, state });
 
         window().location().set_href(&url).ok();
     };
 
-    view! { <button on:click=click>"Connect to spotify"</button> }
+    let i18n = use_i18n();
+    let query_map = use_query_map();
+    let reason = move || query_map.with(|querys| login_reason(querys.get("reason")?));
+
+    view! {
+        <Show when=move || oauth_flow_state.get() == OAuthFlowState::MissingScopes>
+            <p class="login-reason">{move || i18n.t(Msg::MissingScopes)}</p>
+        </Show>
+        {move || reason().map(|reason| view! { <p class="login-reason">{i18n.t(reason)}</p> })}
+        {redirect_error.map(|err| view! { <p class="login-reason">{move || i18n.t(err)}</p> })}
+        <button on:click=click disabled=redirect_error.is_some()>
+            {move || i18n.t(Msg::ConnectToSpotify)}
+        </button>
+        <p>
+            <A href=app_path("/import")>{move || i18n.t(Msg::ViewExport)}</A>
+        </p>
+        <DiagnosticsPanel/>
+    }
 }
 
 #[derive(Default, serde::Deserialize, serde::Serialize, PartialEq, Eq, Clone)]
@@ -112,6 +410,9 @@ enum OAuthFlow {
     FirstVisit,
     RequestedUserAuthorization {
         verifier: String,
+        /// Empty for logins started before this was checked, those have to start over.
+        #[serde(default)]
+        state: String,
     },
     GotToken {
         token: Token,
@@ -122,6 +423,8 @@ enum OAuthFlow {
 enum OAuthFlowState {
     FirstVisit,
     RequestedUserAuthorization,
+    /// The stored token was granted without some of the [`required_scopes`].
+    MissingScopes,
     GotToken,
 }
 
@@ -134,41 +437,96 @@ fn main() {
     let oauth_flow_state = Signal::derive(move || match oauth_flow.get() {
         OAuthFlow::FirstVisit => OAuthFlowState::FirstVisit,
         OAuthFlow::RequestedUserAuthorization { .. } => OAuthFlowState::RequestedUserAuthorization,
+        OAuthFlow::GotToken { token } if !token.scopes.is_superset(&required_scopes()) => {
+            OAuthFlowState::MissingScopes
+        }
         OAuthFlow::GotToken { .. } => OAuthFlowState::GotToken,
     });
 
     let spotify = create_owning_memo(move |old: Option<AuthCodePkceSpotify>| {
         let spotify = match oauth_flow.get() {
             OAuthFlow::FirstVisit => init_spotify(),
-            OAuthFlow::RequestedUserAuthorization { verifier } => {
+            OAuthFlow::RequestedUserAuthorization { verifier, state } => {
                 if let Some(old) = old {
-                    if old.verifier.as_ref() == Some(&verifier) {
+                    if old.verifier.as_ref() == Some(&verifier) && old.oauth.state == state {
                         return (old, false);
                     }
                 }
                 let mut s = init_spotify();
                 s.verifier = Some(verifier);
+                s.oauth.state = state;
+                s
+            }
+            OAuthFlow::GotToken { token } => {
+                // Refreshing the token needs the client id.
+                let mut s = init_spotify();
+                s.token = AuthCodePkceSpotify::from_token(token).token;
                 s
             }
-            OAuthFlow::GotToken { token } => AuthCodePkceSpotify::from_token(token),
         };
         (spotify, true)
     });
 
     provide_context(spotify);
+    let limiter = Limiter::new(MAX_CONCURRENT_REQUESTS).with_rate(MAX_REQUESTS_PER_SECOND);
+    provide_context(limiter.clone());
+    let diagnostics = Diagnostics::from_session_storage();
+    provide_context(diagnostics);
+    let session = Session::new(
+        leptos::Callback::new(move |token| set_oauth_flow(OAuthFlow::GotToken { token })),
+        diagnostics,
+    );
+    provide_context(session);
+
+    let (settings, set_settings, _) = use_local_storage::<Settings, JsonCodec>("settings");
+    provide_context(settings);
+    provide_context(set_settings);
+    provide_context(ReducedMotion(use_media_query(
+        "(prefers-reduced-motion: reduce)",
+    )));
+
+    let current_user: CurrentUserResource = create_local_resource(
+        move || oauth_flow_state.get() == OAuthFlowState::GotToken,
+        move |connected| {
+            let limiter = limiter.clone();
+            async move {
+                if !connected {
+                    return None;
+                }
+                let spotify = spotify.get_untracked();
+                let user = session
+                    .request(&spotify, || {
+                        limiter.run(Priority::Low, spotify.current_user())
+                    })
+                    .await
+                    .ok()?;
+                Some(CurrentUser {
+                    name: display_name(user.display_name, &user.id),
+                    id: user.id,
+                })
+            }
+        },
+    );
+    provide_context(current_user);
 
     mount_to_body(move || {
         view! {
             <div id="root">
                 <Router>
-                    <nav></nav>
+                    <ProgressBar/>
+                    <Nav oauth_flow_state=oauth_flow_state set_oauth_flow=set_oauth_flow/>
                     <main>
                         <Routes>
-                            <Route path="/collab-playlist" view=Outlet>
+                            <Route path=BASE_PATH view=Outlet>
                                 <Route
                                     path="login"
                                     view=move || {
-                                        view! { <Login set_oauth_flow=set_oauth_flow/> }
+                                        view! {
+                                            <Login
+                                                oauth_flow_state=oauth_flow_state
+                                                set_oauth_flow=set_oauth_flow
+                                            />
+                                        }
                                     }
                                 />
 
@@ -184,9 +542,11 @@ fn main() {
                                     }
                                 />
 
+                                <Route path="import" view=Import/>
                                 <Main oauth_flow_state=oauth_flow_state/>
 
                             </Route>
+                            <Route path="/*any" view=NotFound/>
                         </Routes>
                     </main>
                 </Router>
@@ -0,0 +1,161 @@
+use std::cmp::Reverse;
+
+use leptos::{
+    component, create_effect, create_memo, create_signal, expect_context, view, For, IntoView,
+    Show, Signal, SignalGet, SignalSet, SignalUpdate, SignalWith,
+};
+use leptos_use::{use_interval_fn_with_options, utils::Pausable, UseIntervalFnOptions};
+
+use crate::{
+    i18n::{use_i18n, Msg},
+    info::{bucket_totals, fraction, percent, PlaylistInfo},
+    settings::{use_animations, Settings},
+};
+
+/// How long each bucket is shown, in milliseconds.
+const BUCKET_INTERVAL: u64 = 800;
+
+/// Height of a bar, in `em`, the bars are positioned by their rank so they can slide past each
+/// other.
+const BAR_HEIGHT: f64 = 1.6;
+
+/// Animates the running total of every user bucket by bucket, see [`Settings::time_buckets`],
+/// reordering the bars as the lead changes.
+#[component]
+pub fn BarChartRace(#[prop(into)] playlist: Signal<PlaylistInfo>) -> impl IntoView {
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+    let animations = use_animations();
+
+    let buckets = create_memo(move |_| {
+        let time_buckets = settings.with(|s| s.time_buckets);
+        playlist.with(|playlist| bucket_totals(playlist, time_buckets))
+    });
+    let (step, set_step) = create_signal(0usize);
+    let last_step = move || buckets.with(|(_, buckets)| buckets.len().saturating_sub(1));
+
+    let Pausable {
+        pause,
+        resume,
+        is_active,
+    } = use_interval_fn_with_options(
+        move || set_step.update(|step| *step = (*step + 1).min(last_step())),
+        BUCKET_INTERVAL,
+        UseIntervalFnOptions::default().immediate(false),
+    );
+
+    {
+        let pause = pause.clone();
+        create_effect(move |_| {
+            if step() >= last_step() && is_active() {
+                pause();
+            }
+        });
+    }
+
+    let toggle = move |_| {
+        if is_active.get() {
+            pause();
+        } else {
+            if step() >= last_step() {
+                set_step.set(0);
+            }
+            resume();
+        }
+    };
+
+    // Ranks and widths of every user in the shown bucket, ranked by their total at that point.
+    let standings = create_memo(move |_| {
+        buckets.with(|(_, buckets)| {
+            let Some(bucket) = buckets.get(step().min(last_step())) else {
+                return Vec::new();
+            };
+            playlist.with(|playlist| {
+                let mut users = playlist
+                    .users
+                    .iter()
+                    .map(|user| {
+                        let total = bucket.totals.get(&user.id).copied().unwrap_or_default();
+                        (user.id.clone(), total)
+                    })
+                    .collect::<Vec<_>>();
+                users.sort_by_key(|&(_, total)| Reverse(total));
+                let leader = users.first().map(|&(_, total)| total).unwrap_or_default();
+                users
+                    .into_iter()
+                    .enumerate()
+                    .map(|(rank, (id, total))| (id, rank, fraction(total, leader)))
+                    .collect::<Vec<_>>()
+            })
+        })
+    });
+
+    view! {
+        <Show when=move || buckets.with(|(_, buckets)| !buckets.is_empty())>
+            <details class="race">
+                <summary>{move || i18n.t(Msg::ContributionRace)}</summary>
+                <div class="race-controls">
+                    <button on:click=toggle.clone()>
+                        {move || i18n.t(if is_active() { Msg::Pause } else { Msg::Play })}
+                    </button>
+                    <span class="race-bucket">
+                        {move || {
+                            buckets
+                                .with(|(bucket, buckets)| {
+                                    buckets
+                                        .get(step())
+                                        .map(|totals| bucket.label(totals.start))
+                                })
+                        }}
+
+                    </span>
+                </div>
+                <div
+                    class="race-bars"
+                    class:race-animated=animations
+                    style:height=move || {
+                        playlist.with(|playlist| format!("{}em", playlist.users.len() as f64 * BAR_HEIGHT))
+                    }
+                >
+
+                    <For
+                        each=move || {
+                            playlist
+                                .with(|playlist| {
+                                    playlist.users.to_vec()
+                                })
+                        }
+
+                        key=|user| user.id.clone()
+                        let:user
+                    >
+                        {
+                            let id = user.id.clone();
+                            let color = user.color.to_string();
+                            let standing = create_memo(move |_| {
+                                standings
+                                    .with(|standings| {
+                                        standings
+                                            .iter()
+                                            .find(|(user, _, _)| *user == id)
+                                            .map(|&(_, rank, size)| (rank, size))
+                                            .unwrap_or_default()
+                                    })
+                            });
+                            view! {
+                                <div
+                                    class="race-bar"
+                                    style:top=move || format!("{}em", standing().0 as f64 * BAR_HEIGHT)
+                                    style:width=move || percent(standing().1)
+                                    style=("--color", color)
+                                >
+                                    {user.name}
+                                </div>
+                            }
+                        }
+                    </For>
+                </div>
+            </details>
+        </Show>
+    }
+}
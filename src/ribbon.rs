@@ -0,0 +1,564 @@
+use std::collections::HashMap;
+
+use leptos::{
+    component, create_effect, create_memo, create_node_ref, create_signal, ev::KeyboardEvent,
+    expect_context, html, view, wasm_bindgen::JsCast, window, For, IntoView, RwSignal, Show,
+    Signal, SignalGet, SignalSet, SignalUpdate, SignalWith, SignalWithUntracked, WriteSignal,
+};
+use web_sys::HtmlElement;
+
+use crate::{
+    app_path,
+    i18n::{use_i18n, Msg},
+    info::{
+        aged, album_runs, mix, percent, text_color, Duplicate, Extreme, PlaylistInfo, TrackInfo,
+        UserInfo, UserKey, WHITE,
+    },
+    settings::{use_animations, HeaderLabel, RibbonOrder, Settings},
+};
+
+/// Renders the tracks of the playlist side by side, sized by their duration, under a header with
+/// the users that added them.
+///
+/// When `highlighted` is set, the tracks of every other user are dimmed. While `hovered` is set,
+/// it is shown instead, hovering a header sets it. Clicking a header toggles `focused`, which the
+/// caller uses to only pass in that user. Setting `jump_to` scrolls that user's header into view.
+#[component]
+pub fn Ribbon(
+    #[prop(into)] playlist: Signal<PlaylistInfo>,
+    highlighted: RwSignal<Option<UserKey>>,
+    hovered: RwSignal<Option<UserKey>>,
+    focused: RwSignal<Option<UserKey>>,
+    #[prop(into)] jump_to: Signal<Option<UserKey>>,
+) -> impl IntoView {
+    let settings = expect_context::<Signal<Settings>>();
+    let animations = use_animations();
+    // Read out by screen readers when a track gets focus.
+    let (announcement, announce) = create_signal(String::new());
+
+    // The cells are keyed so they keep their DOM node, and thus can transition, when the ribbon is
+    // recomputed. Every cell looks up its current info in these maps.
+    let users = create_memo(move |_| {
+        playlist.with(|playlist| {
+            playlist
+                .users
+                .iter()
+                .map(|user| (user.id.clone(), user.clone()))
+                .collect::<HashMap<_, _>>()
+        })
+    });
+    let tracks = create_memo(move |_| {
+        playlist.with(|playlist| {
+            playlist
+                .tracks
+                .iter()
+                .map(|track| (track.index, track.clone()))
+                .collect::<HashMap<_, _>>()
+        })
+    });
+    // Like the track table, neighbouring albums alternate between plain and tinted. Every run gets
+    // one label under the tracks, as wide as the tracks together.
+    let group_albums = move || settings.with(|s| s.group_albums);
+    let albums = create_memo(move |_| {
+        playlist.with(|playlist| {
+            let runs = album_runs(&playlist.tracks);
+            let mut group = 0;
+            let mut tinted = HashMap::new();
+            let mut labels = Vec::new();
+            for (i, (track, run)) in playlist.tracks.iter().zip(runs).enumerate() {
+                if run > 0 {
+                    group += 1;
+                    let width = playlist.tracks[i..i + run]
+                        .iter()
+                        .map(|track| track.relative_size)
+                        .sum::<f64>();
+                    labels.push((track.album.clone(), width, group % 2 == 0));
+                }
+                tinted.insert(track.index, group % 2 == 0);
+            }
+            (tinted, labels)
+        })
+    });
+
+    view! {
+        <div
+            class="ribon"
+            class:ribon-animated=animations
+            class:ribon-fresh=move || settings.with(|s| s.fresh_emphasis)
+            class:ribon-outline-duplicates=move || settings.with(|s| s.outline_duplicates)
+            class:ribon-highlighting=move || is_highlighting(highlighted, hovered)
+            role="table"
+            aria-label=move || playlist.with(|playlist| playlist.name.clone())
+        >
+            <Show when=move || settings.with(|s| s.ribbon_order == RibbonOrder::Contributor)>
+                <div class="ribon-user-row" role="row">
+                    <For
+                        each=move || {
+                            playlist
+                                .with(|playlist| {
+                                    playlist.users.iter().map(|user| user.id.clone()).collect::<Vec<_>>()
+                                })
+                        }
+
+                        key=|id| id.clone()
+                        let:id
+                    >
+                        <UserCell
+                            user=create_memo(move |_| {
+                                users.with(|users| users.get(&id).cloned().unwrap_or_default())
+                            })
+
+                            highlighted=highlighted
+                            hovered=hovered
+                            focused=focused
+                            jump_to=jump_to
+                        />
+                    </For>
+                </div>
+            </Show>
+            <div class="ribon-track-row" role="row">
+                <For
+                    each=move || {
+                        playlist
+                            .with(|playlist| {
+                                playlist.tracks.iter().map(|track| track.index).collect::<Vec<_>>()
+                            })
+                    }
+
+                    key=|index| *index
+                    let:index
+                >
+                    {
+                        let track = create_memo(move |_| {
+                            tracks.with(|tracks| tracks.get(&index).cloned().unwrap_or_default())
+                        });
+                        let contributor = move || {
+                            let user = track.with(|track| track.user.clone());
+                            users
+                                .with(|users| users.get(&user).map(|user| user.name.clone()))
+                                .unwrap_or_default()
+                        };
+                        let tinted = move || {
+                            group_albums()
+                                && albums.with(|(tinted, _)| tinted.get(&index).copied().unwrap_or_default())
+                        };
+                        view! {
+                            <TrackCell
+                                track=track
+                                contributor=Signal::derive(contributor)
+                                tinted=Signal::derive(tinted)
+                                highlighted=highlighted
+                                hovered=hovered
+                                announce=announce
+                            />
+                        }
+                    }
+                </For>
+            </div>
+            <Show when=group_albums>
+                <div class="ribon-album-row">
+                    {move || {
+                        albums
+                            .with(|(_, labels)| {
+                                labels
+                                    .iter()
+                                    .map(|(album, width, tinted)| {
+                                        view! {
+                                            <div
+                                                class="ribon-album"
+                                                class:ribon-album-tinted=*tinted
+                                                style:width=percent(*width)
+                                                title=album.clone()
+                                            >
+                                                {album.clone()}
+                                            </div>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()
+                            })
+                    }}
+                </div>
+            </Show>
+            <div class="visually-hidden" aria-live="polite">{announcement}</div>
+            <Axis playlist=playlist/>
+        </div>
+    }
+}
+
+/// The users side by side as one stacked bar, sized like their headers in [`Ribbon`]. Much lighter
+/// than the full ribbon for big playlists. Clicking a user highlights them.
+#[component]
+pub fn CompactRibbon(
+    #[prop(into)] playlist: Signal<PlaylistInfo>,
+    highlighted: RwSignal<Option<UserKey>>,
+    hovered: RwSignal<Option<UserKey>>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let animations = use_animations();
+
+    view! {
+        <div
+            class="ribon ribon-compact"
+            class:ribon-animated=animations
+            class:ribon-highlighting=move || is_highlighting(highlighted, hovered)
+        >
+            {move || {
+                playlist
+                    .with(|playlist| {
+                        playlist
+                            .users
+                            .iter()
+                            .map(|user| {
+                                let id = user.id.clone();
+                                let duration = i18n.duration(&user.total_duration);
+                                let percentage = i18n.locale().percentage(user.relative_size);
+                                let title = format!(
+                                    "{}: {} ({percentage}%)",
+                                    user.name,
+                                    i18n.fill(Msg::SegmentTitle, &[&duration, &user.amount_of_tracks]),
+                                );
+                                let toggle = {
+                                    let id = id.clone();
+                                    move |_| {
+                                        highlighted
+                                            .update(|highlighted| {
+                                                *highlighted = match highlighted.take() {
+                                                    Some(current) if current == id => None,
+                                                    _ => Some(id.clone()),
+                                                };
+                                            })
+                                    }
+                                };
+                                let hover = {
+                                    let id = id.clone();
+                                    move |_| hovered.set(Some(id.clone()))
+                                };
+                                view! {
+                                    <div
+                                        class="ribon-compact-segment"
+                                        class:ribon-highlighted=move || is_highlighted(highlighted, hovered, &id)
+                                        style:width=percent(user.relative_size)
+                                        style=("--color", user.color.to_string())
+                                        title=title
+                                        on:click=toggle
+                                        on:mouseenter=hover
+                                        on:mouseleave=move |_| hovered.set(None)
+                                    ></div>
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                    })
+            }}
+        </div>
+    }
+}
+
+/// Where the quarter ticks of the axis go.
+const AXIS_TICKS: [f64; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+/// Marks the cumulative share along the ribbon, with a mark where each user's tracks end when they
+/// are grouped per user.
+#[component]
+fn Axis(#[prop(into)] playlist: Signal<PlaylistInfo>) -> impl IntoView {
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+
+    // Without the user headers the tracks of a user aren't together, so there is nothing to mark.
+    let boundaries = move || {
+        if settings.with(|s| s.ribbon_order != RibbonOrder::Contributor) {
+            return Vec::new();
+        }
+        playlist.with(|playlist| {
+            let mut cumulative = 0.0;
+            playlist
+                .users
+                .iter()
+                .map(|user| {
+                    cumulative += user.relative_size;
+                    let title = format!("{}%", i18n.locale().percentage(cumulative));
+                    view! {
+                        <span
+                            class="ribon-axis-boundary"
+                            style:left=percent(cumulative.min(1.0))
+                            title=title
+                        ></span>
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    view! {
+        <div class="ribon-axis">
+            {boundaries}
+            {AXIS_TICKS
+                .into_iter()
+                .map(|tick| {
+                    view! {
+                        <span
+                            class="ribon-axis-tick"
+                            style:left=percent(tick)
+                            style=("--tick", tick.to_string())
+                        >
+                            {move || format!("{}%", i18n.locale().number(tick * 100.0))}
+                        </span>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </div>
+    }
+}
+
+pub fn is_highlighting(
+    highlighted: RwSignal<Option<UserKey>>,
+    hovered: RwSignal<Option<UserKey>>,
+) -> bool {
+    hovered.with(Option::is_some) || highlighted.with(Option::is_some)
+}
+
+/// The hovered user takes precedence over the highlighted one.
+pub fn is_highlighted(
+    highlighted: RwSignal<Option<UserKey>>,
+    hovered: RwSignal<Option<UserKey>>,
+    user: &UserKey,
+) -> bool {
+    hovered.with(|hovered| match hovered {
+        Some(hovered) => hovered == user,
+        None => highlighted.with(|highlighted| highlighted.as_ref() == Some(user)),
+    })
+}
+
+#[component]
+fn UserCell(
+    #[prop(into)] user: Signal<UserInfo>,
+    highlighted: RwSignal<Option<UserKey>>,
+    hovered: RwSignal<Option<UserKey>>,
+    focused: RwSignal<Option<UserKey>>,
+    #[prop(into)] jump_to: Signal<Option<UserKey>>,
+) -> impl IntoView {
+    let node_ref = create_node_ref::<html::Div>();
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+
+    create_effect(move |_| {
+        let Some(target) = jump_to.get() else {
+            return;
+        };
+        if user.with_untracked(|user| user.id == target) {
+            if let Some(cell) = node_ref.get_untracked() {
+                cell.scroll_into_view();
+            }
+        }
+    });
+
+    let label = move || {
+        let duration = user.with(|user| i18n.duration(&user.total_duration));
+        let percentage = user.with(|user| i18n.locale().percentage(user.relative_size));
+        match settings.with(|s| s.header_label) {
+            HeaderLabel::Duration => duration,
+            HeaderLabel::Percentage => format!("{percentage}%"),
+            HeaderLabel::Both => i18n.fill(Msg::UserTime, &[&duration, &percentage]),
+        }
+    };
+    let toggle_focus = move |_| {
+        let id = user.with_untracked(|user| user.id.clone());
+        focused.update(|focused| {
+            *focused = match focused.take() {
+                Some(current) if current == id => None,
+                _ => Some(id),
+            };
+        });
+    };
+    // The text sits where the header has faded to about half its color.
+    let text = move || user.with(|user| text_color(mix(user.color, WHITE, 0.5)));
+    // Too narrow for the text, which would overflow into the neighbouring headers.
+    let collapsed = move || {
+        let threshold = settings.with(|s| s.header_threshold) as f64 / 100.0;
+        user.with(|user| user.relative_size < threshold)
+    };
+
+    view! {
+        <div
+            class="ribon-user"
+            role="columnheader"
+            class:ribon-highlighted=move || {
+                user.with(|user| is_highlighted(highlighted, hovered, &user.id))
+            }
+            on:mouseenter=move |_| hovered.set(Some(user.with_untracked(|user| user.id.clone())))
+            on:mouseleave=move |_| hovered.set(None)
+            on:click=toggle_focus
+            class:ribon-user-collapsed=collapsed
+            class:ribon-user-failed=move || user.with(|user| user.lookup_failed)
+            node_ref=node_ref
+            style:width=move || user.with(|user| percent(user.relative_size))
+            style=("--color", move || user.with(|user| user.color.to_string()))
+            style=("--text-color", text)
+            title=move || collapsed().then(|| format!("{} {}", user.with(|user| user.name.clone()), label()))
+        >
+            <div class="ribon-user-cell">
+                {move || {
+                    user.with(|user| match &user.avatar {
+                        Some(avatar) => {
+                            view! { <img class="ribon-user-avatar" src=avatar.clone() alt=""/> }
+                                .into_view()
+                        }
+                        None => {
+                            let initial = user.name.chars().next().unwrap_or('?');
+                            let initial = initial.to_uppercase().to_string();
+                            view! {
+                                <span class="ribon-user-avatar ribon-user-initial">{initial}</span>
+                            }
+                                .into_view()
+                        }
+                    })
+                }}
+                <span class="ribon-user-name">{move || user.with(|user| user.name.clone())}</span>
+                <span class="ribon-user-time">{label}</span>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn TrackCell(
+    #[prop(into)] track: Signal<TrackInfo>,
+    #[prop(into)] contributor: Signal<String>,
+    #[prop(into)] tinted: Signal<bool>,
+    highlighted: RwSignal<Option<UserKey>>,
+    hovered: RwSignal<Option<UserKey>>,
+    announce: WriteSignal<String>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+    let cobweb =
+        move || !settings.with(|s| s.fresh_emphasis) && track.with(|track| track.age > 0.99);
+
+    let title = move || {
+        track.with(|track| {
+            let notes = [
+                (track.is_episode, Msg::PodcastEpisode),
+                (track.is_recent, Msg::RecentTrack),
+            ]
+            .into_iter()
+            .filter(|&(applies, _)| applies)
+            .map(|(_, msg)| i18n.t(msg))
+            .collect::<Vec<_>>();
+            let mut title = track.name.clone();
+            if !track.artists.is_empty() {
+                title = format!("{title} — {}", track.artists.join(", "));
+            }
+            if !notes.is_empty() {
+                title = format!("{title} ({})", notes.join(", "));
+            }
+            title
+        })
+    };
+
+    // The cells are in the tab order in the order they are shown, the arrow keys move between
+    // neighbours and enter opens the track like clicking does.
+    let on_keydown = move |ev: KeyboardEvent| {
+        let Some(cell) = ev
+            .current_target()
+            .and_then(|target| target.dyn_into::<HtmlElement>().ok())
+        else {
+            return;
+        };
+        let next = match ev.key().as_str() {
+            "ArrowLeft" => cell.previous_element_sibling(),
+            "ArrowRight" => cell.next_element_sibling(),
+            "Enter" => {
+                if let Some(url) = track.with_untracked(|track| track.url.clone()) {
+                    let _ = window().open_with_url_and_target(&url, "_blank");
+                }
+                return;
+            }
+            _ => return,
+        };
+        ev.prevent_default();
+        if let Some(next) = next.and_then(|next| next.dyn_into::<HtmlElement>().ok()) {
+            let _ = next.focus();
+        }
+    };
+    let on_focus = move |_| announce.set(format!("{}, {}", title(), contributor.get()));
+
+    view! {
+        <div
+            class="ribon-track-cell"
+            class:ribon-highlighted=move || {
+                track.with(|track| is_highlighted(highlighted, hovered, &track.user))
+            }
+
+            class:ribon-track-tinted=tinted
+            class:ribon-track-recent=move || track.with(|track| track.is_recent)
+            class:ribon-track-episode=move || track.with(|track| track.is_episode)
+            class:ribon-track-duplicate=move || {
+                track.with(|track| track.duplicate == Some(Duplicate::Exact))
+            }
+            class:ribon-track-possible-duplicate=move || {
+                track.with(|track| track.duplicate == Some(Duplicate::Possible))
+            }
+
+            style:width=move || track.with(|track| percent(track.relative_size))
+            style=("--color", move || track.with(|track| track.color.to_string()))
+            style=("--age", move || track.with(|track| percent(track.age / 2.0)))
+            style=(
+                "--text-color",
+                move || {
+                    let fresh_emphasis = settings.with(|s| s.fresh_emphasis);
+                    track.with(|track| text_color(aged(track.color, track.age, fresh_emphasis)))
+                },
+            )
+            title=title
+            role="cell"
+            tabindex="0"
+            on:keydown=on_keydown
+            on:focus=on_focus
+        >
+            <Show when=cobweb>
+                <img
+                    class="ribon-track-cobweb ribon-track-cobweb-top"
+                    src=app_path("/cobweb-top.png")
+                />
+            </Show>
+            {move || {
+                track
+                    .with(|track| track.extreme)
+                    .map(|extreme| {
+                        let (badge, msg) = match extreme {
+                            Extreme::Longest => ("▲", Msg::LongestTrack),
+                            Extreme::Shortest => ("▼", Msg::ShortestTrack),
+                        };
+                        view! {
+                            <span class="ribon-track-extreme" title=move || i18n.t(msg)>
+                                {badge}
+                            </span>
+                        }
+                    })
+            }}
+
+            <div class="ribon-track-name">{move || track.with(|track| track.name.clone())}</div>
+            // Covers the whole cell, so even the narrowest tracks can be clicked. Keyboards go
+            // through the cell itself.
+            {move || {
+                track
+                    .with(|track| track.url.clone())
+                    .map(|url| {
+                        view! {
+                            <a
+                                class="ribon-track-link"
+                                href=url
+                                tabindex="-1"
+                                target="_blank"
+                                rel="noopener noreferrer"
+                                aria-label=move || track.with(|track| track.name.clone())
+                            ></a>
+                        }
+                    })
+            }}
+
+            <Show when=cobweb>
+                <img class="ribon-track-cobweb" src=app_path("/cobweb.png")/>
+            </Show>
+        </div>
+    }
+}
@@ -0,0 +1,442 @@
+use leptos::{
+    component, event_target_checked, event_target_value, expect_context, view, IntoView, Show,
+    Signal, SignalGet, SignalUpdate, SignalWith, WriteSignal,
+};
+
+use crate::i18n::{use_i18n, Locale, Msg};
+
+/// Whether the system asks for as little motion as possible, follows the OS setting live.
+#[derive(Debug, Clone, Copy)]
+pub struct ReducedMotion(pub Signal<bool>);
+
+/// Whether changes may be animated, which the system can veto regardless of
+/// [`Settings::animations`].
+pub fn use_animations() -> Signal<bool> {
+    let settings = expect_context::<Signal<Settings>>();
+    let ReducedMotion(reduced_motion) = expect_context();
+    Signal::derive(move || settings.with(|s| s.animations) && !reduced_motion.get())
+}
+
+/// User preferences, persisted in local storage under `settings`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub locale: Locale,
+    /// Animate the ribbon cells when they change size or visibility.
+    pub animations: bool,
+    pub header_label: HeaderLabel,
+    /// How totals are formatted.
+    pub duration_style: DurationStyle,
+    /// Tracks added less than this many days ago glow, `0` turns this off.
+    pub recent_days: u32,
+    pub track_coloring: TrackColoring,
+    /// Users with less than this percentage of the playlist get a header without text.
+    pub header_threshold: u32,
+    /// Mixed into the color of every user, changing it shuffles the palette.
+    pub color_seed: u32,
+    /// Take the user colors from a fixed colorblind safe palette instead of generating them.
+    pub colorblind_palette: bool,
+    /// Fade old tracks out instead of drawing cobwebs on them, so the fresh ones stand out.
+    pub fresh_emphasis: bool,
+    /// How many days after being added a track gets the most cobwebs, or is faded out the most.
+    pub old_after_days: u32,
+    pub ribbon_size: RibbonSize,
+    pub ribbon_order: RibbonOrder,
+    pub time_buckets: TimeBuckets,
+    pub ribbon_view: RibbonView,
+    /// Order the users from big to small, instead of small to big.
+    pub biggest_first: bool,
+    /// Users past this many, the smallest ones, are merged into one, `0` turns this off.
+    pub max_contributors: u32,
+    pub episodes: EpisodeStats,
+    /// Outline the tracks that are in the playlist more than once.
+    pub outline_duplicates: bool,
+    /// Set the tracks of the same album apart, in the ribbon, its SVG export and the track table.
+    pub group_albums: bool,
+    /// Only list the collaborative playlists on the main page.
+    pub collaborative_only: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            locale: Locale::default(),
+            animations: true,
+            header_label: HeaderLabel::default(),
+            duration_style: DurationStyle::default(),
+            recent_days: 7,
+            track_coloring: TrackColoring::default(),
+            header_threshold: 1,
+            color_seed: 0,
+            colorblind_palette: false,
+            fresh_emphasis: false,
+            old_after_days: 200,
+            ribbon_size: RibbonSize::default(),
+            ribbon_order: RibbonOrder::default(),
+            time_buckets: TimeBuckets::default(),
+            ribbon_view: RibbonView::default(),
+            biggest_first: false,
+            max_contributors: 40,
+            episodes: EpisodeStats::default(),
+            outline_duplicates: false,
+            group_albums: false,
+            collaborative_only: true,
+        }
+    }
+}
+
+/// What the user headers above the ribbon show next to the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum HeaderLabel {
+    Duration,
+    Percentage,
+    #[default]
+    Both,
+}
+
+/// How total durations, like the ones in the user headers, are formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum DurationStyle {
+    /// `1:23:45`
+    #[default]
+    Clock,
+    /// `1h 23m`
+    Human,
+}
+
+/// What the color of a track in the ribbon shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum TrackColoring {
+    /// The color of the user that added it.
+    #[default]
+    User,
+    /// From deep cuts to hits.
+    Popularity,
+    /// From just added to [`Settings::old_after_days`] old, whoever added it.
+    Age,
+}
+
+/// How the playlist is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum RibbonView {
+    /// Every track, grouped per user.
+    #[default]
+    Tracks,
+    /// A single stacked bar of the users, much lighter than every track for big playlists.
+    StackedBar,
+    /// Every track at the day it was added.
+    Timeline,
+}
+
+/// What the width of a track in the ribbon is proportional to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum RibbonSize {
+    #[default]
+    Duration,
+    /// Newer tracks are wider.
+    Recency,
+}
+
+/// In what order the ribbon lays out the tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum RibbonOrder {
+    /// Grouped per user, under their header.
+    #[default]
+    Contributor,
+    /// In the order they were added, tracks without an `added_at` last. The user headers are left
+    /// out since a user's tracks are spread over the whole ribbon.
+    Added,
+}
+
+/// How podcast episodes count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum EpisodeStats {
+    /// Like any track.
+    #[default]
+    Include,
+    /// Only in a summary of their own, so the music numbers aren't skewed by hour long episodes.
+    Separate,
+}
+
+/// How the time based charts group tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum TimeBuckets {
+    Day,
+    Week,
+    Month,
+    /// Depending on how long the playlist has been active.
+    #[default]
+    Auto,
+}
+
+/// A checkbox bound to one of the settings.
+fn setting_checkbox(
+    label: Msg,
+    get: fn(&Settings) -> bool,
+    set: fn(&mut Settings, bool),
+) -> impl IntoView {
+    let settings = expect_context::<Signal<Settings>>();
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+    let i18n = use_i18n();
+
+    view! {
+        <label>
+            <input
+                type="checkbox"
+                prop:checked=move || settings.with(get)
+                on:change=move |ev| set_settings.update(|s| set(s, event_target_checked(&ev)))
+            />
+
+            {move || i18n.t(label)}
+        </label>
+    }
+}
+
+/// A slider bound to one of the settings, `label` takes the current value.
+fn setting_slider(
+    label: Msg,
+    min: u32,
+    max: u32,
+    get: fn(&Settings) -> u32,
+    set: fn(&mut Settings, u32),
+) -> impl IntoView {
+    let settings = expect_context::<Signal<Settings>>();
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+    let i18n = use_i18n();
+
+    view! {
+        <label class="setting-slider">
+            {move || i18n.fill(label, &[&settings.with(get)])}
+            <input
+                type="range"
+                min=min
+                max=max
+                prop:value=move || settings.with(get).to_string()
+                on:input=move |ev| {
+                    if let Ok(value) = event_target_value(&ev).parse() {
+                        set_settings.update(|s| set(s, value));
+                    }
+                }
+            />
+        </label>
+    }
+}
+
+/// A number input bound to one of the settings.
+fn setting_number(
+    label: Msg,
+    get: fn(&Settings) -> u32,
+    set: fn(&mut Settings, u32),
+) -> impl IntoView {
+    let settings = expect_context::<Signal<Settings>>();
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+    let i18n = use_i18n();
+
+    view! {
+        <label>
+            {move || i18n.t(label)}
+            <input
+                type="number"
+                min="0"
+                prop:value=move || settings.with(get).to_string()
+                on:change=move |ev| {
+                    if let Ok(value) = event_target_value(&ev).parse() {
+                        set_settings.update(|s| set(s, value));
+                    }
+                }
+            />
+        </label>
+    }
+}
+
+/// A select bound to one of the settings, offering `options` with their label.
+fn setting_select<T: Copy + PartialEq + 'static>(
+    label: Msg,
+    options: &'static [(T, Msg)],
+    get: fn(&Settings) -> T,
+    set: fn(&mut Settings, T),
+) -> impl IntoView {
+    let settings = expect_context::<Signal<Settings>>();
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+    let i18n = use_i18n();
+
+    view! {
+        <label>
+            {move || i18n.t(label)}
+            <select on:change=move |ev| {
+                let option = event_target_value(&ev).parse::<usize>().ok().and_then(|i| options.get(i));
+                if let Some(&(value, _)) = option {
+                    set_settings.update(|s| set(s, value));
+                }
+            }>
+                {options
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(value, msg))| {
+                        view! {
+                            <option
+                                value=i.to_string()
+                                prop:selected=move || settings.with(|s| get(s) == value)
+                            >
+                                {move || i18n.t(msg)}
+                            </option>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+            </select>
+        </label>
+    }
+}
+
+#[component]
+pub fn SettingsPanel() -> impl IntoView {
+    let settings = expect_context::<Signal<Settings>>();
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+    let i18n = use_i18n();
+
+    view! {
+        <details class="settings">
+            <summary>{move || i18n.t(Msg::Settings)}</summary>
+            <label>
+                {move || i18n.t(Msg::Language)}
+                <select on:change=move |ev| {
+                    let name = event_target_value(&ev);
+                    if let Some(locale) = Locale::ALL.into_iter().find(|l| l.name() == name) {
+                        set_settings.update(|s| s.locale = locale);
+                    }
+                }>
+                    {Locale::ALL
+                        .into_iter()
+                        .map(|locale| {
+                            view! {
+                                <option
+                                    value=locale.name()
+                                    prop:selected=move || settings.with(|s| s.locale == locale)
+                                >
+                                    {locale.name()}
+                                </option>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </select>
+            </label>
+            {setting_checkbox(Msg::Animations, |s| s.animations, |s, v| s.animations = v)}
+            {setting_select(
+                Msg::HeaderLabel,
+                &[
+                    (HeaderLabel::Duration, Msg::HeaderLabelDuration),
+                    (HeaderLabel::Percentage, Msg::HeaderLabelPercentage),
+                    (HeaderLabel::Both, Msg::HeaderLabelBoth),
+                ],
+                |s| s.header_label,
+                |s, v| s.header_label = v,
+            )}
+            {setting_select(
+                Msg::DurationStyle,
+                &[
+                    (DurationStyle::Clock, Msg::DurationStyleClock),
+                    (DurationStyle::Human, Msg::DurationStyleHuman),
+                ],
+                |s| s.duration_style,
+                |s, v| s.duration_style = v,
+            )}
+            {setting_checkbox(Msg::BiggestFirst, |s| s.biggest_first, |s, v| s.biggest_first = v)}
+            {setting_select(
+                Msg::RibbonView,
+                &[
+                    (RibbonView::Tracks, Msg::RibbonViewTracks),
+                    (RibbonView::StackedBar, Msg::RibbonViewStackedBar),
+                    (RibbonView::Timeline, Msg::RibbonViewTimeline),
+                ],
+                |s| s.ribbon_view,
+                |s, v| s.ribbon_view = v,
+            )}
+            {setting_checkbox(
+                Msg::FreshEmphasis,
+                |s| s.fresh_emphasis,
+                |s, v| s.fresh_emphasis = v,
+            )}
+            {setting_slider(
+                Msg::OldAfterDays,
+                7,
+                1000,
+                |s| s.old_after_days,
+                |s, v| s.old_after_days = v,
+            )}
+            {setting_select(
+                Msg::RibbonSize,
+                &[
+                    (RibbonSize::Duration, Msg::RibbonSizeDuration),
+                    (RibbonSize::Recency, Msg::RibbonSizeRecency),
+                ],
+                |s| s.ribbon_size,
+                |s, v| s.ribbon_size = v,
+            )}
+            {setting_select(
+                Msg::RibbonOrder,
+                &[
+                    (RibbonOrder::Contributor, Msg::RibbonOrderContributor),
+                    (RibbonOrder::Added, Msg::RibbonOrderAdded),
+                ],
+                |s| s.ribbon_order,
+                |s, v| s.ribbon_order = v,
+            )}
+            {setting_select(
+                Msg::TimeBuckets,
+                &[
+                    (TimeBuckets::Auto, Msg::TimeBucketsAuto),
+                    (TimeBuckets::Day, Msg::TimeBucketsDay),
+                    (TimeBuckets::Week, Msg::TimeBucketsWeek),
+                    (TimeBuckets::Month, Msg::TimeBucketsMonth),
+                ],
+                |s| s.time_buckets,
+                |s, v| s.time_buckets = v,
+            )}
+            {setting_select(
+                Msg::EpisodeStats,
+                &[
+                    (EpisodeStats::Include, Msg::EpisodeStatsInclude),
+                    (EpisodeStats::Separate, Msg::EpisodeStatsSeparate),
+                ],
+                |s| s.episodes,
+                |s, v| s.episodes = v,
+            )}
+            {setting_number(Msg::RecentDays, |s| s.recent_days, |s, v| s.recent_days = v)}
+            {setting_number(
+                Msg::MaxContributors,
+                |s| s.max_contributors,
+                |s, v| s.max_contributors = v,
+            )}
+            {setting_number(
+                Msg::HeaderThreshold,
+                |s| s.header_threshold,
+                |s, v| s.header_threshold = v,
+            )}
+            {setting_select(
+                Msg::TrackColoring,
+                &[
+                    (TrackColoring::User, Msg::TrackColoringUser),
+                    (TrackColoring::Popularity, Msg::TrackColoringPopularity),
+                    (TrackColoring::Age, Msg::TrackColoringAge),
+                ],
+                |s| s.track_coloring,
+                |s, v| s.track_coloring = v,
+            )}
+            {setting_checkbox(
+                Msg::ColorblindPalette,
+                |s| s.colorblind_palette,
+                |s, v| s.colorblind_palette = v,
+            )}
+            <div class="settings-colors">
+                <button on:click=move |_| {
+                    set_settings.update(|s| s.color_seed = s.color_seed.wrapping_add(1))
+                }>{move || i18n.t(Msg::ShuffleColors)}</button>
+                <Show when=move || settings.with(|s| s.color_seed != 0)>
+                    <button on:click=move |_| {
+                        set_settings.update(|s| s.color_seed = 0)
+                    }>{move || i18n.t(Msg::ResetColors)}</button>
+                </Show>
+            </div>
+        </details>
+    }
+}
@@ -0,0 +1,47 @@
+use leptos::{component, create_signal, view, window, IntoView, Show, SignalUpdate};
+use qrcode::{render::svg, QrCode};
+
+use crate::i18n::{use_i18n, Msg};
+
+/// Longer urls make for a QR code too dense to scan from a screen.
+const MAX_QR_URL_LENGTH: usize = 500;
+
+/// The current url, or just the page without its query, like the filter of the playlists, if that
+/// gets too long. The settings are kept in local storage, they don't go along.
+fn share_url() -> Option<String> {
+    let location = window().location();
+    let url = location.href().ok()?;
+    if url.len() <= MAX_QR_URL_LENGTH {
+        return Some(url);
+    }
+    Some(format!(
+        "{}{}",
+        location.origin().ok()?,
+        location.pathname().ok()?
+    ))
+}
+
+fn qr_svg(url: &str) -> Option<String> {
+    let code = QrCode::new(url.as_bytes()).ok()?;
+    Some(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+#[component]
+pub fn ShareQr() -> impl IntoView {
+    let i18n = use_i18n();
+    let (open, set_open) = create_signal(false);
+
+    view! {
+        <div class="share-qr">
+            <button on:click=move |_| set_open.update(|open| *open = !*open)>
+                {move || i18n.t(if open() { Msg::HideQr } else { Msg::ShowQr })}
+            </button>
+            <Show when=open>
+                <div
+                    class="share-qr-popover"
+                    inner_html=share_url().and_then(|url| qr_svg(&url)).unwrap_or_default()
+                ></div>
+            </Show>
+        </div>
+    }
+}
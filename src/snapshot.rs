@@ -0,0 +1,155 @@
+use std::{
+    borrow::Borrow,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use leptos::{component, view, IntoView, SignalGetUntracked, SignalSet};
+use leptos_use::{storage::use_local_storage, utils::JsonCodec};
+use rspotify::model::{FullPlaylist, PlayableItem, UserId};
+
+use crate::{
+    i18n::{use_i18n, Msg},
+    info::UserLookup,
+};
+
+/// The tracks of a playlist as they were the last time it was viewed. Taken from the playlist as
+/// Spotify has it rather than from its [`PlaylistInfo`](crate::info::PlaylistInfo), which changes
+/// with the settings.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Snapshot {
+    tracks: Vec<SnapshotTrack>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+struct SnapshotTrack {
+    /// The track id, or the name for local files.
+    key: String,
+    name: String,
+    /// The name of who added it, empty for tracks without `added_by`.
+    added_by: String,
+    /// The id of who added it, `None` for tracks without `added_by`.
+    user: Option<String>,
+}
+
+impl Snapshot {
+    pub fn new(playlist: &FullPlaylist, user_names: &HashMap<UserId<'static>, UserLookup>) -> Self {
+        let tracks = playlist
+            .tracks
+            .items
+            .iter()
+            .filter_map(|item| {
+                let (key, name) = match item.track.as_ref()? {
+                    PlayableItem::Track(track) => (
+                        track
+                            .id
+                            .as_ref()
+                            .map(|id| Borrow::<str>::borrow(id).to_owned())
+                            .unwrap_or_else(|| track.name.clone()),
+                        track.name.clone(),
+                    ),
+                    PlayableItem::Episode(episode) => (
+                        Borrow::<str>::borrow(&episode.id).to_owned(),
+                        episode.name.clone(),
+                    ),
+                };
+                let user = item.added_by.as_ref().map(|user| &user.id);
+                let added_by = match user.and_then(|id| user_names.get(id)) {
+                    Some(UserLookup::Found { name, .. }) => name.clone(),
+                    _ => user
+                        .map(|id| Borrow::<str>::borrow(id).to_owned())
+                        .unwrap_or_default(),
+                };
+                Some(SnapshotTrack {
+                    key,
+                    name,
+                    added_by,
+                    user: user.map(|id| Borrow::<str>::borrow(id).to_owned()),
+                })
+            })
+            .collect();
+        Self { tracks }
+    }
+
+    /// The tracks of `self` that are not in `other`, a track someone else added again counts as
+    /// another track.
+    fn missing_from<'a>(&'a self, other: &Snapshot) -> Vec<&'a SnapshotTrack> {
+        let keys = other
+            .tracks
+            .iter()
+            .map(|track| (&track.key, &track.user))
+            .collect::<HashSet<_>>();
+        self.tracks
+            .iter()
+            .filter(|track| !keys.contains(&(&track.key, &track.user)))
+            .collect()
+    }
+}
+
+thread_local! {
+    /// The stored snapshot of every playlist shown since the page loaded, as it was before it got
+    /// replaced. A retry or any other render compares against this again, and not against the
+    /// snapshot it just stored.
+    static PREVIOUS: RefCell<HashMap<String, Option<Snapshot>>> = RefCell::default();
+}
+
+/// Shows which tracks were added and removed since the playlist was last viewed, and then stores
+/// the current tracks for the next visit. Only the first render after the page loaded stores them.
+#[component]
+pub fn SnapshotDiff(playlist_id: String, current: Snapshot) -> impl IntoView {
+    let i18n = use_i18n();
+    let (snapshot, set_snapshot, _) =
+        use_local_storage::<Option<Snapshot>, JsonCodec>(format!("snapshot-{playlist_id}"));
+
+    let previous = PREVIOUS.with_borrow_mut(|previous| {
+        previous
+            .entry(playlist_id)
+            .or_insert_with(|| {
+                let stored = snapshot.get_untracked();
+                set_snapshot.set(Some(current.clone()));
+                stored
+            })
+            .clone()
+    });
+
+    // Without a previous snapshot this is the first visit, there is nothing to compare to.
+    let previous = previous?;
+    let added = current.missing_from(&previous);
+    let removed = previous.missing_from(&current);
+
+    if added.is_empty() && removed.is_empty() {
+        return Some(
+            view! { <p class="snapshot-diff">{move || i18n.t(Msg::SnapshotUnchanged)}</p> }
+                .into_view(),
+        );
+    }
+
+    let summary = i18n.fill(Msg::SnapshotChanges, &[&added.len(), &removed.len()]);
+    let items = added
+        .into_iter()
+        .map(|track| (Msg::SnapshotAdded, track))
+        .chain(
+            removed
+                .into_iter()
+                .map(|track| (Msg::SnapshotRemoved, track)),
+        )
+        .map(|(msg, track)| {
+            let added_by = if track.added_by.is_empty() {
+                i18n.t(Msg::UnknownUser)
+            } else {
+                track.added_by.as_str()
+            };
+            view! { <li>{i18n.fill(msg, &[&track.name, &added_by])}</li> }
+        })
+        .collect::<Vec<_>>();
+
+    Some(
+        view! {
+            <details class="snapshot-diff">
+                <summary>{summary}</summary>
+                <ul>{items}</ul>
+            </details>
+        }
+        .into_view(),
+    )
+}
@@ -0,0 +1,251 @@
+use leptos::{
+    component, create_memo, event_target_checked, expect_context, view, IntoView, RwSignal, Signal,
+    SignalGet, SignalGetUntracked, SignalSet, SignalUpdate, SignalWith, WriteSignal,
+};
+
+use chrono::TimeDelta;
+
+use crate::{
+    i18n::{use_i18n, Msg},
+    info::{display_duration, Duplicates, Extreme, PlaylistInfo, UserKey},
+    settings::Settings,
+};
+
+/// The size of the playlist at a glance. Users merged past [`Settings::max_contributors`] count as
+/// one contributor.
+///
+/// [`Settings::max_contributors`]: crate::settings::Settings::max_contributors
+#[component]
+pub fn Stats(#[prop(into)] playlist: Signal<PlaylistInfo>) -> impl IntoView {
+    let i18n = use_i18n();
+
+    let stats = move || {
+        playlist.with(|playlist| {
+            let tracks = playlist.tracks.len();
+            let contributors = playlist
+                .users
+                .iter()
+                .filter(|user| user.id.is_some())
+                .count();
+            let average = match tracks {
+                0 => TimeDelta::zero(),
+                tracks => playlist.total_duration / tracks as i32,
+            };
+            [
+                i18n.fill(
+                    Msg::StatsDuration,
+                    &[&display_duration(&playlist.total_duration)],
+                ),
+                i18n.fill(Msg::StatsTracks, &[&tracks]),
+                i18n.fill(Msg::StatsContributors, &[&contributors]),
+                i18n.fill(Msg::StatsAverage, &[&display_duration(&average)]),
+            ]
+            .into_iter()
+            .map(|stat| view! { <span class="stats-item">{stat}</span> })
+            .collect::<Vec<_>>()
+        })
+    };
+
+    view! { <div class="stats">{stats}</div> }
+}
+
+/// A few facts about the playlist, shown above the ribbon.
+#[component]
+pub fn Summary(
+    #[prop(into)] playlist: Signal<PlaylistInfo>,
+    highlighted: RwSignal<Option<UserKey>>,
+    jump_to: WriteSignal<Option<UserKey>>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+
+    let top_contributor = create_memo(move |_| {
+        playlist.with(|playlist| {
+            playlist
+                .users
+                .iter()
+                .filter(|user| user.id.is_some() && !user.merged)
+                .max_by(|a, b| a.total_duration.cmp(&b.total_duration))
+                .map(|user| (user.id.clone(), user.name.clone(), user.color))
+        })
+    });
+
+    // Only meaningful with someone to compare to.
+    let most_obscure = move || {
+        playlist.with(|playlist| {
+            let users = playlist
+                .users
+                .iter()
+                .filter(|user| user.id.is_some() && !user.merged)
+                .filter_map(|user| Some((user, user.average_popularity?)))
+                .collect::<Vec<_>>();
+            if users.len() < 2 {
+                return None;
+            }
+            users
+                .into_iter()
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(user, popularity)| {
+                    let popularity = i18n.locale().number(popularity.round());
+                    view! {
+                        <span class="summary-item">
+                            {i18n.t(Msg::MostObscure)}
+                            {user.name.clone()}
+                            " ("
+                            {i18n.fill(Msg::AveragePopularity, &[&popularity])}
+                            ")"
+                        </span>
+                    }
+                })
+        })
+    };
+
+    // Who added the copies goes in the title, the summary is crowded enough.
+    let duplicates = move || {
+        playlist.with(|playlist| {
+            let Duplicates { exact, possible } = playlist.duplicates;
+            if exact == 0 && possible == 0 {
+                return None;
+            }
+            let mut text = Vec::new();
+            if exact > 0 {
+                text.push(i18n.fill(Msg::DuplicateTracks, &[&exact]));
+            }
+            if possible > 0 {
+                text.push(i18n.fill(Msg::PossibleDuplicates, &[&possible]));
+            }
+            let title = playlist
+                .users
+                .iter()
+                .filter(|user| user.duplicates > 0)
+                .map(|user| format!("{} ({})", user.name, user.duplicates))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some(view! {
+                <span class="summary-item summary-duplicates" title=title>
+                    {text.join(", ")}
+                    <label>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || settings.with(|s| s.outline_duplicates)
+                            on:change=move |ev| {
+                                set_settings
+                                    .update(|s| s.outline_duplicates = event_target_checked(&ev))
+                            }
+                        />
+
+                        {move || i18n.t(Msg::OutlineDuplicates)}
+                    </label>
+                </span>
+            })
+        })
+    };
+
+    let extreme_track = move |extreme: Extreme, msg: Msg| {
+        playlist.with(|playlist| {
+            playlist
+                .tracks
+                .iter()
+                .find(|track| track.extreme == Some(extreme))
+                .map(|track| {
+                    view! {
+                        <span class="summary-item">
+                            {i18n.fill(msg, &[&track.name, &display_duration(&track.duration)])}
+                        </span>
+                    }
+                })
+        })
+    };
+
+    view! {
+        <div class="summary">
+            {move || {
+                top_contributor
+                    .get()
+                    .map(|(id, name, color)| {
+                        let jump = move |_| {
+                            if highlighted.get_untracked().as_ref() == Some(&id) {
+                                highlighted.set(None);
+                            } else {
+                                highlighted.set(Some(id.clone()));
+                                jump_to.set(Some(id.clone()));
+                            }
+                        };
+                        view! {
+                            <span class="summary-item">
+                                {move || i18n.t(Msg::TopContributor)}
+                                <button
+                                    class="summary-top-contributor"
+                                    style=("--color", color.to_string())
+                                    on:click=jump
+                                >
+                                    {name}
+                                </button>
+                            </span>
+                        }
+                    })
+            }}
+            {move || extreme_track(Extreme::Longest, Msg::SummaryLongest)}
+            {move || extreme_track(Extreme::Shortest, Msg::SummaryShortest)}
+            {most_obscure}
+            {move || {
+                playlist
+                    .with(|playlist| {
+                        playlist
+                            .most_added
+                            .as_ref()
+                            .map(|most_added| {
+                                let text = i18n
+                                    .fill(
+                                        Msg::MostAdded,
+                                        &[
+                                            &most_added.name,
+                                            &most_added.count,
+                                            &most_added.users.len(),
+                                            &most_added.users.join(", "),
+                                        ],
+                                    );
+                                view! { <span class="summary-item">{text}</span> }
+                            })
+                    })
+            }}
+            {move || {
+                let active_days = playlist.with(|playlist| playlist.active_days);
+                (active_days > 0)
+                    .then(|| {
+                        view! {
+                            <span class="summary-item">
+                                {i18n.fill(Msg::ActiveDays, &[&active_days])}
+                            </span>
+                        }
+                    })
+            }}
+            {move || {
+                playlist
+                    .with(|playlist| playlist.busiest_day)
+                    .map(|busiest_day| {
+                        let date = busiest_day.date.format("%Y-%m-%d").to_string();
+                        view! {
+                            <span class="summary-item">
+                                {i18n.fill(Msg::BusiestDay, &[&date, &busiest_day.count])}
+                            </span>
+                        }
+                    })
+            }}
+            {duplicates}
+            {move || {
+                playlist
+                    .with(|playlist| playlist.podcasts)
+                    .map(|podcasts| {
+                        let duration = i18n.duration(&podcasts.total_duration);
+                        view! {
+                            <span class="summary-item">
+                                {i18n.fill(Msg::PodcastsSummary, &[&podcasts.episodes, &duration])}
+                            </span>
+                        }
+                    })
+            }}
+        </div>
+    }
+}
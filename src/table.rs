@@ -0,0 +1,289 @@
+use std::cmp::Ordering;
+
+use leptos::{
+    component, create_memo, create_signal, event_target_checked, expect_context, view, IntoView,
+    Show, Signal, SignalGet, SignalSet, SignalUpdate, SignalWith, WriteSignal,
+};
+use leptos_use::{use_clipboard, UseClipboardReturn};
+
+use crate::{
+    i18n::{use_i18n, Msg},
+    info::{album_runs, display_duration, PlaylistInfo, TrackInfo, UserInfo},
+    settings::Settings,
+};
+
+/// A column the table can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    /// The order of the ribbon.
+    Ribbon,
+    Position,
+    Age,
+}
+
+impl SortBy {
+    fn compare(self, a: &TrackInfo, b: &TrackInfo) -> Ordering {
+        match self {
+            SortBy::Ribbon => Ordering::Equal,
+            SortBy::Position => a.index.cmp(&b.index),
+            // Tracks without an age go last, regardless of the direction.
+            SortBy::Age => match (a.age_days, b.age_days) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        }
+    }
+}
+
+/// A column the user table can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UserSortBy {
+    Name,
+    Tracks,
+    Duration,
+    /// The order of the ribbon, biggest first.
+    Share,
+}
+
+impl UserSortBy {
+    fn compare(self, a: &UserInfo, b: &UserInfo) -> Ordering {
+        match self {
+            UserSortBy::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            UserSortBy::Tracks => a.amount_of_tracks.cmp(&b.amount_of_tracks),
+            UserSortBy::Duration => a.total_duration.cmp(&b.total_duration),
+            UserSortBy::Share => b.relative_size.total_cmp(&a.relative_size),
+        }
+    }
+}
+
+/// One line per track, `Artist — Title`, for pasting elsewhere.
+fn track_list(tracks: &[TrackInfo]) -> String {
+    tracks
+        .iter()
+        .map(|track| {
+            if track.artists.is_empty() {
+                track.name.clone()
+            } else {
+                format!("{} — {}", track.artists.join(", "), track.name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Sorts by `column`, or flips the direction if it already was.
+fn sort_by<T: PartialEq>(set_sort: WriteSignal<(T, bool)>, column: T) {
+    set_sort.update(|(sort, descending)| {
+        if *sort == column {
+            *descending = !*descending;
+        } else {
+            *sort = column;
+            *descending = false;
+        }
+    });
+}
+
+/// Every track in the order of the ribbon, numbered by its position in the playlist so it can be
+/// found back in Spotify.
+#[component]
+pub fn TrackTable(#[prop(into)] playlist: Signal<PlaylistInfo>) -> impl IntoView {
+    let i18n = use_i18n();
+    let settings = expect_context::<Signal<Settings>>();
+    let set_settings = expect_context::<WriteSignal<Settings>>();
+    let (sort, set_sort) = create_signal((SortBy::Ribbon, false));
+    let UseClipboardReturn {
+        is_supported,
+        copied,
+        copy,
+        ..
+    } = use_clipboard();
+    let (copied_count, set_copied_count) = create_signal(0);
+
+    let tracks = create_memo(move |_| {
+        let (sort, descending) = sort();
+        playlist.with(|playlist| {
+            let mut tracks = playlist.tracks.clone();
+            tracks.sort_by(|a, b| {
+                let order = sort.compare(a, b);
+                let unknown_age =
+                    sort == SortBy::Age && (a.age_days.is_none() || b.age_days.is_none());
+                if descending && !unknown_age {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+            tracks
+        })
+    });
+
+    let copy_tracks = move |_| {
+        tracks.with(|tracks| {
+            copy(&track_list(tracks));
+            set_copied_count.set(tracks.len());
+        });
+    };
+
+    let rows = move || {
+        let group_albums = settings.with(|s| s.group_albums);
+        playlist.with(|playlist| {
+            tracks.with(|tracks| {
+                let runs = album_runs(tracks);
+                // Alternates between groups, so neighbouring albums can be told apart.
+                let mut group = 0;
+                tracks
+                    .iter()
+                    .zip(runs)
+                    .map(|(track, run)| {
+                        let album = if !group_albums {
+                            Some(view! { <td>{track.album.clone()}</td> })
+                        } else if run > 0 {
+                            group += 1;
+                            Some(view! {
+                                <td class="track-table-album" rowspan=run.to_string()>
+                                    {track.album.clone()}
+                                </td>
+                            })
+                        } else {
+                            None
+                        };
+                        let tinted = group_albums && group % 2 == 0;
+                        let user = playlist
+                            .users
+                            .iter()
+                            .find(|user| user.id == track.user)
+                            .map(|user| user.name.clone())
+                            .unwrap_or_default();
+                        let age = if track.just_now {
+                            Some(i18n.t(Msg::JustNow).to_string())
+                        } else {
+                            track.age_days.map(|days| days.to_string())
+                        };
+                        view! {
+                            <tr
+                                class:track-table-tinted=tinted
+                                style=("--color", track.color.to_string())
+                            >
+                                // Spotify numbers tracks from 1.
+                                <td class="track-table-index">{track.index + 1}</td>
+                                <td>{track.name.clone()}</td>
+                                {album}
+                                <td class="track-table-user">{user}</td>
+                                <td>{display_duration(&track.duration)}</td>
+                                <td class="track-table-age">{age}</td>
+                            </tr>
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+    };
+
+    view! {
+        <details class="track-table">
+            <summary>{move || i18n.t(Msg::Tracks)}</summary>
+            <Show when=is_supported>
+                <div class="track-table-copy">
+                    <button on:click=copy_tracks.clone()>{move || i18n.t(Msg::CopyTrackList)}</button>
+                    <Show when=copied>
+                        <span>{move || i18n.fill(Msg::CopiedTracks, &[&copied_count.get()])}</span>
+                    </Show>
+                </div>
+            </Show>
+            <label class="track-table-group">
+                <input
+                    type="checkbox"
+                    prop:checked=move || settings.with(|s| s.group_albums)
+                    on:change=move |ev| {
+                        set_settings.update(|s| s.group_albums = event_target_checked(&ev))
+                    }
+                />
+                {move || i18n.t(Msg::GroupByAlbum)}
+            </label>
+            <table>
+                <thead>
+                    <tr>
+                        <th class="track-table-sortable" on:click=move |_| sort_by(set_sort, SortBy::Position)>
+                            "#"
+                        </th>
+                        <th>{move || i18n.t(Msg::TrackName)}</th>
+                        <th>{move || i18n.t(Msg::Album)}</th>
+                        <th>{move || i18n.t(Msg::AddedBy)}</th>
+                        <th>{move || i18n.t(Msg::Duration)}</th>
+                        <th class="track-table-sortable" on:click=move |_| sort_by(set_sort, SortBy::Age)>
+                            {move || i18n.t(Msg::AgeDays)}
+                        </th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        </details>
+    }
+}
+
+/// The numbers behind the ribbon headers, one row per user, readable no matter how narrow their
+/// header is.
+#[component]
+pub fn UserTable(#[prop(into)] playlist: Signal<PlaylistInfo>) -> impl IntoView {
+    let i18n = use_i18n();
+    let (sort, set_sort) = create_signal((UserSortBy::Share, false));
+
+    let rows = move || {
+        let (sort, descending) = sort();
+        playlist.with(|playlist| {
+            let mut users = playlist.users.iter().collect::<Vec<_>>();
+            users.sort_by(|a, b| {
+                let order = sort.compare(a, b);
+                if descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+            users
+                .into_iter()
+                .map(|user| {
+                    let percentage = i18n.locale().percentage(user.relative_size);
+                    view! {
+                        <tr>
+                            <td class="user-table-name">
+                                <span
+                                    class="user-table-swatch"
+                                    style=("--color", user.color.to_string())
+                                ></span>
+                                {user.name.clone()}
+                            </td>
+                            <td class="user-table-number">{user.amount_of_tracks}</td>
+                            <td class="user-table-number">{i18n.duration(&user.total_duration)}</td>
+                            <td class="user-table-number">{format!("{percentage}%")}</td>
+                        </tr>
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let header = move |column: UserSortBy, msg: Msg| {
+        view! {
+            <th class="track-table-sortable" on:click=move |_| sort_by(set_sort, column)>
+                {move || i18n.t(msg)}
+            </th>
+        }
+    };
+
+    view! {
+        <table class="user-table">
+            <thead>
+                <tr>
+                    {header(UserSortBy::Name, Msg::Contributor)}
+                    {header(UserSortBy::Tracks, Msg::Tracks)}
+                    {header(UserSortBy::Duration, Msg::Duration)}
+                    {header(UserSortBy::Share, Msg::HeaderLabelPercentage)}
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
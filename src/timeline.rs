@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use leptos::{component, view, IntoView, RwSignal, Show, Signal, SignalSet, SignalWith};
+
+use crate::{
+    i18n::{use_i18n, Msg},
+    info::{percent, PlaylistInfo, TrackInfo, UserKey},
+    ribbon::{is_highlighted, is_highlighting},
+};
+
+/// How far, in `em`, a dot sits above the one added earlier on the same day.
+const STACK_STEP: f64 = 0.5;
+
+/// Days with more tracks than this are cut off, or a single import would dwarf everything else.
+const MAX_STACK: usize = 24;
+
+/// Every track as a dot at the day it was added, tracks added on the same day stacked on top of
+/// each other so bursts of activity stand out. Tracks without an `added_at` get a lane of their own.
+#[component]
+pub fn Timeline(
+    #[prop(into)] playlist: Signal<PlaylistInfo>,
+    highlighted: RwSignal<Option<UserKey>>,
+    hovered: RwSignal<Option<UserKey>>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+
+    // Left offset and height in the stack of every dated track, and how high the stacks get.
+    let dated = move || {
+        playlist.with(|playlist| {
+            let mut tracks = playlist
+                .tracks
+                .iter()
+                .filter_map(|track| Some((track.added_at?, track)))
+                .collect::<Vec<_>>();
+            tracks.sort_by_key(|&(added_at, track)| (added_at, track.index));
+            let (Some(&(first, _)), Some(&(last, _))) = (tracks.first(), tracks.last()) else {
+                return (Vec::new(), 0);
+            };
+            let first = first.date_naive();
+            let span = (last.date_naive() - first).num_days().max(1) as f64;
+
+            let mut stacks = HashMap::new();
+            let dots = tracks
+                .into_iter()
+                .map(|(added_at, track)| {
+                    let day = (added_at.date_naive() - first).num_days();
+                    let stack = stacks.entry(day).or_insert(0);
+                    let height = (*stack).min(MAX_STACK);
+                    *stack += 1;
+                    (day as f64 / span, height, added_at, track.clone())
+                })
+                .collect::<Vec<_>>();
+            let highest = stacks.into_values().max().unwrap_or(0).min(MAX_STACK + 1);
+            (dots, highest)
+        })
+    };
+    let undated = move || {
+        playlist.with(|playlist| {
+            playlist
+                .tracks
+                .iter()
+                .filter(|track| track.added_at.is_none())
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let dot = move |track: &TrackInfo, title: String| {
+        let id = track.user.clone();
+        let hover = {
+            let id = id.clone();
+            move |_| hovered.set(Some(id.clone()))
+        };
+        view! {
+            <span
+                class="timeline-dot"
+                class:ribon-highlighted=move || is_highlighted(highlighted, hovered, &id)
+                style=("--color", track.color.to_string())
+                title=title
+                on:mouseenter=hover
+                on:mouseleave=move |_| hovered.set(None)
+            ></span>
+        }
+    };
+
+    view! {
+        <div class="timeline" class:ribon-highlighting=move || is_highlighting(highlighted, hovered)>
+            {move || {
+                let (dots, highest) = dated();
+                let dates = dots
+                    .first()
+                    .zip(dots.last())
+                    .map(|((_, _, first, _), (_, _, last, _))| {
+                        view! {
+                            <div class="timeline-dates">
+                                <span>{first.format("%Y-%m-%d").to_string()}</span>
+                                <span>{last.format("%Y-%m-%d").to_string()}</span>
+                            </div>
+                        }
+                    });
+                let dots = dots
+                    .into_iter()
+                    .map(|(left, height, added_at, track)| {
+                        let title = format!("{} ({})", track.name, added_at.format("%Y-%m-%d"));
+                        view! {
+                            <span
+                                class="timeline-slot"
+                                style:left=percent(left)
+                                style:bottom=format!("{}em", height as f64 * STACK_STEP)
+                            >
+                                {dot(&track, title)}
+                            </span>
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                view! {
+                    <div
+                        class="timeline-lane"
+                        style:height=format!("{}em", (highest as f64 + 1.0) * STACK_STEP)
+                    >
+                        {dots}
+                    </div>
+                    {dates}
+                }
+            }}
+            <Show when=move || !undated().is_empty()>
+                <div class="timeline-undated">
+                    <span class="timeline-undated-label">{move || i18n.t(Msg::UndatedTracks)}</span>
+                    {move || {
+                        undated()
+                            .iter()
+                            .map(|track| dot(track, track.name.clone()))
+                            .collect::<Vec<_>>()
+                    }}
+                </div>
+            </Show>
+        </div>
+    }
+}